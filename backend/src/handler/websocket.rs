@@ -7,11 +7,21 @@ use axum::{
     },
     response::IntoResponse,
 };
-use futures::StreamExt;
-use lib::message::ClientMessage;
+use futures::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use lib::{
+    codec::Codec,
+    message::{ClientMessage, ServerMessage},
+};
 use tracing::log;
 
-use crate::server::{user::User, ServerApp};
+use crate::server::{
+    protocol::{self, ProtocolError, Violations},
+    user::User,
+    ServerApp,
+};
 
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
@@ -22,40 +32,91 @@ pub async fn websocket_handler(
 }
 
 async fn websocket(stream: WebSocket, server_app: Arc<ServerApp>) {
-    let (sender, mut receiver) = stream.split();
+    let (mut sender, mut receiver) = stream.split();
+
+    let Some(features) = handshake(&mut sender, &mut receiver).await else {
+        return;
+    };
 
     let mut room_id = None;
     let mut user_id = None;
+    let mut since = 0;
+    let mut violations = Violations::default();
+    let codec = Codec::negotiated(&features);
     while let Some(Ok(message)) = receiver.next().await {
-        if let Message::Text(message) = message {
-            let message: ClientMessage = serde_json::from_str(&message).unwrap();
-            match message {
-                ClientMessage::Join(room_id_inner, user_id_inner) => {
-                    let user_id_inner: Arc<str> = Arc::from(user_id_inner);
-                    if !server_app.check_exist_room(&room_id_inner).await {
-                        let room_id_inner: Arc<str> = Arc::from(room_id_inner);
-                        if server_app.make_room(room_id_inner.clone()).await.is_ok() {
-                            room_id = Some(room_id_inner);
-                            user_id = Some(user_id_inner);
-                            break;
-                        } else {
-                            return;
-                        }
-                    } else {
-                        room_id = Some(Arc::from(room_id_inner));
-                        user_id = Some(user_id_inner);
-                        break;
+        let Some(frame) = protocol::to_frame(message) else {
+            continue;
+        };
+        let error = match protocol::parse(codec, &frame) {
+            Ok(ClientMessage::Join(room_id_inner, user_id_inner, since_inner)) => {
+                let room_id_inner: Arc<str> = Arc::from(room_id_inner);
+                if !server_app.check_exist_room(&room_id_inner).await {
+                    if let Err(err) = server_app.make_room(room_id_inner.clone()).await {
+                        log::error!("{err}");
+                        let error = ProtocolError::RoomUnavailable(err.to_string());
+                        send(&mut sender, codec, error.to_message(Some(&frame))).await;
+                        let _ = sender.send(Message::Close(None)).await;
+                        return;
                     }
                 }
-                _ => {
-                    return;
-                }
+                room_id = Some(room_id_inner);
+                user_id = Some(Arc::from(user_id_inner));
+                since = since_inner;
+                break;
             }
+            Ok(_) => ProtocolError::Unexpected("join a room first"),
+            Err(error) => error,
+        };
+
+        log::warn!("rejected message before join: {error}");
+        send(&mut sender, codec, error.to_message(Some(&frame))).await;
+        if violations.add() {
+            let _ = sender.send(protocol::violation_close()).await;
+            return;
         }
     }
 
     if let (Some(user_id), Some(room_id)) = (user_id, room_id) {
-        let user = User::new(user_id, sender, receiver);
-        let _ = server_app.join_room(room_id, user).await;
+        let user = User::new(user_id, sender, receiver, features);
+        if let Err(err) = server_app.join_room(room_id, user, since).await {
+            log::error!("{err}");
+        }
+    }
+}
+
+//The first text frame has to be a Hello the server can work with. Returns the
+//negotiated features, or None after closing the socket.
+async fn handshake(
+    sender: &mut SplitSink<WebSocket, Message>,
+    receiver: &mut SplitStream<WebSocket>,
+) -> Option<Vec<String>> {
+    while let Some(Ok(message)) = receiver.next().await {
+        let frame = match message {
+            Message::Close(_) => return None,
+            message => match protocol::to_frame(message) {
+                Some(frame) => frame,
+                None => continue,
+            },
+        };
+
+        match protocol::negotiate(&frame) {
+            Ok((welcome, features)) => {
+                send(sender, Codec::Json, welcome).await;
+                return Some(features);
+            }
+            Err(error) => {
+                log::warn!("handshake failed: {error}");
+                send(sender, Codec::Json, error.to_message(Some(&frame))).await;
+                let _ = sender.send(protocol::handshake_close()).await;
+                return None;
+            }
+        }
+    }
+    None
+}
+
+async fn send(sender: &mut SplitSink<WebSocket, Message>, codec: Codec, message: ServerMessage) {
+    if let Some(message) = protocol::encode(codec, &message) {
+        let _ = sender.send(message).await;
     }
 }
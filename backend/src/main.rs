@@ -3,7 +3,7 @@ use axum::http::{Request, StatusCode};
 use axum::response::Response;
 use axum::routing::post;
 use axum::{routing::get, Router};
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use handler::api::{check_room_exist_handler, check_user_exist_handler};
 use handler::websocket::websocket_handler;
 use lib::{IP_ADDRESS, PORT};
@@ -11,16 +11,19 @@ use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+use storage::StorageKind;
 use tokio::fs;
 use tower::{ServiceBuilder, ServiceExt};
 use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
 use tracing::log;
 
-use crate::server::ServerApp;
+use crate::server::{Heartbeat, ServerApp};
 
 mod handler;
 mod server;
+mod storage;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(name = "server", about = "A server for our wasm project!")]
@@ -32,11 +35,48 @@ struct Opt {
     /// set the directory where static files are to be found
     #[clap(long = "static-dir", default_value = "../dist")]
     static_dir: String,
+
+    /// set where rooms are stored
+    #[clap(long = "storage", value_enum, default_value = "json")]
+    storage: StorageKind,
+
+    /// set the storage path, a directory for json and a database file for sqlite
+    #[clap(long = "storage-path")]
+    storage_path: Option<PathBuf>,
+
+    /// set how many hours an empty room is kept before it is deleted
+    #[clap(long = "retention-hours", default_value = "168")]
+    retention_hours: u64,
+
+    /// set how many seconds pass between pings to each user
+    #[clap(
+        long = "heartbeat-interval",
+        default_value = "15",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    heartbeat_interval: u64,
+
+    /// set how many seconds a user may stay silent before being dropped from the room,
+    /// longer than the interval
+    #[clap(long = "heartbeat-timeout", default_value = "45")]
+    heartbeat_timeout: u64,
 }
 
 #[tokio::main]
 async fn main() {
     let opt = Opt::parse();
+    //A user answering every ping would still be dropped between two of them.
+    if opt.heartbeat_timeout <= opt.heartbeat_interval {
+        Opt::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--heartbeat-timeout ({}) has to be longer than --heartbeat-interval ({})",
+                    opt.heartbeat_timeout, opt.heartbeat_interval
+                ),
+            )
+            .exit();
+    }
 
     // Setup logging & RUST_LOG from args
     if std::env::var("RUST_LOG").is_err() {
@@ -45,7 +85,22 @@ async fn main() {
     // enable console logging
     tracing_subscriber::fmt::init();
 
-    let server_app = Arc::new(ServerApp::new());
+    let storage_path = opt
+        .storage_path
+        .clone()
+        .unwrap_or_else(|| opt.storage.default_path());
+    let storage = opt
+        .storage
+        .open(storage_path.clone())
+        .unwrap_or_else(|err| panic!("Unable to open storage {storage_path:?}: {err}"));
+    log::info!("storing rooms in {storage_path:?}");
+
+    let retention = Duration::from_secs(opt.retention_hours * 60 * 60);
+    let heartbeat = Heartbeat {
+        interval: Duration::from_secs(opt.heartbeat_interval),
+        timeout: Duration::from_secs(opt.heartbeat_timeout),
+    };
+    let server_app = Arc::new(ServerApp::new(storage, retention, heartbeat));
 
     let app = using_serve_dir(opt.clone(), server_app);
 
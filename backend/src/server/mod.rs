@@ -1,53 +1,154 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 
+use lib::operation::Sequence;
 use tokio::sync::{
     mpsc::{self, Receiver, Sender},
     Mutex,
 };
 use tracing::log;
 
-use self::{room::Room, user::User};
+use crate::storage::{self, RoomMetadata, Storage, StorageError};
 
+use self::{operation_log::OperationLog, room::Room, user::User};
+
+pub mod operation_log;
+pub mod protocol;
 pub mod room;
+pub mod room_state;
 pub mod user;
 
+//How often stored rooms are checked against the retention period.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+//How often rooms ping their users, and how long a user may stay silent before
+//being dropped from the room.
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
 #[derive(Debug)]
 pub enum ServerAppMessage {
-    DeleteRoom(Arc<str>),
+    //The last user left. The room stays in storage until the retention period passes.
+    UnloadRoom(Arc<str>),
 }
 
 #[derive(Debug)]
 pub enum ServerAppError {
     RoomAlreadyExist(Arc<str>),
     RoomDoesNotExist(Arc<str>),
+    Storage(StorageError),
+}
+
+impl fmt::Display for ServerAppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerAppError::RoomAlreadyExist(room_id) => {
+                write!(f, "room already exists room_id = {room_id}")
+            }
+            ServerAppError::RoomDoesNotExist(room_id) => {
+                write!(f, "room does not exist room_id = {room_id}")
+            }
+            ServerAppError::Storage(err) => write!(f, "{err}"),
+        }
+    }
 }
 
+impl From<StorageError> for ServerAppError {
+    fn from(err: StorageError) -> Self {
+        ServerAppError::Storage(err)
+    }
+}
+
+//Rooms in memory are the ones with users, every room is also kept in storage
+//and loaded back when someone joins it again.
 pub struct ServerApp {
     rooms: Arc<Mutex<HashMap<Arc<str>, Room>>>,
+    //Held while a room is loaded from storage or rooms are deleted from it, so that
+    //a room is never loaded twice or deleted while it loads. The rooms stay unlocked.
+    loading: Arc<Mutex<()>>,
     sender: Sender<ServerAppMessage>,
+    storage: Arc<dyn Storage>,
+    heartbeat: Heartbeat,
 }
 
 impl ServerApp {
-    pub fn new() -> Self {
+    pub fn new(storage: Arc<dyn Storage>, retention: Duration, heartbeat: Heartbeat) -> Self {
         let (sender, receiver) = mpsc::channel(1000);
         let app = ServerApp {
             rooms: Arc::new(Mutex::new(HashMap::new())),
+            loading: Arc::new(Mutex::new(())),
             sender,
+            storage,
+            heartbeat,
         };
 
         app.run(receiver);
+        app.purge_expired_rooms(retention);
 
         app
     }
 
     fn run(&self, mut receiver: Receiver<ServerAppMessage>) {
         let rooms_clone = self.rooms.clone();
+        let storage_clone = self.storage.clone();
         tokio::spawn(async move {
             while let Some(message) = receiver.recv().await {
                 match message {
-                    ServerAppMessage::DeleteRoom(id) => {
-                        log::info!("Delete Room room_id = {id}");
+                    ServerAppMessage::UnloadRoom(id) => {
+                        log::info!("Unload Room room_id = {id}");
                         rooms_clone.lock().await.remove(&id);
+                        let now = storage::now();
+                        let touched = storage::run_blocking(&storage_clone, {
+                            let id = id.clone();
+                            move |storage| storage.touch_room(&id, now)
+                        })
+                        .await;
+                        if let Err(err) = touched {
+                            log::error!("failed to touch room room_id = {id}: {err}");
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    //Deletes stored rooms nobody has used for the retention period.
+    fn purge_expired_rooms(&self, retention: Duration) {
+        let rooms_clone = self.rooms.clone();
+        let loading_clone = self.loading.clone();
+        let storage_clone = self.storage.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                let before = storage::now().saturating_sub(retention.as_secs());
+                let _loading_lock = loading_clone.lock().await;
+                let expired = storage::run_blocking(&storage_clone, move |storage| {
+                    storage.inactive_rooms(before)
+                })
+                .await;
+                let expired = match expired {
+                    Ok(expired) => expired,
+                    Err(err) => {
+                        log::error!("failed to list inactive rooms: {err}");
+                        continue;
+                    }
+                };
+                for id in expired {
+                    if rooms_clone.lock().await.contains_key(&*id) {
+                        continue;
+                    }
+                    log::info!("Delete Room room_id = {id}");
+                    let deleted = storage::run_blocking(&storage_clone, {
+                        let id = id.clone();
+                        move |storage| storage.delete_room(&id)
+                    })
+                    .await;
+                    if let Err(err) = deleted {
+                        log::error!("failed to delete room room_id = {id}: {err}");
                     }
                 }
             }
@@ -56,12 +157,34 @@ impl ServerApp {
 
     pub async fn make_room(&self, room_id: Arc<str>) -> Result<(), ServerAppError> {
         log::info!("Make room room_id = {room_id}");
+        //Held across the storage calls, so two makes of the same id cannot both pass the checks.
         let mut rooms_write = self.rooms.lock().await;
         if rooms_write.get(&*room_id).is_some() {
             return Err(ServerAppError::RoomAlreadyExist(room_id));
         }
 
-        let new_room = Room::new(room_id.clone(), self.sender.clone());
+        let created = storage::run_blocking(&self.storage, {
+            let room_id = room_id.clone();
+            move |storage| {
+                if storage.has_room(&room_id)? {
+                    return Ok(false);
+                }
+                storage.create_room(&RoomMetadata::new(&room_id))?;
+                Ok(true)
+            }
+        })
+        .await?;
+        if !created {
+            return Err(ServerAppError::RoomAlreadyExist(room_id));
+        }
+
+        let new_room = Room::new(
+            room_id.clone(),
+            self.sender.clone(),
+            OperationLog::new(),
+            self.storage.clone(),
+            self.heartbeat,
+        );
 
         rooms_write.insert(room_id, new_room);
 
@@ -69,7 +192,17 @@ impl ServerApp {
     }
 
     pub async fn check_exist_room(&self, room_id: &str) -> bool {
-        self.rooms.lock().await.get(room_id).is_some()
+        if self.rooms.lock().await.get(room_id).is_some() {
+            return true;
+        }
+
+        match self.stored(room_id).await {
+            Ok(exists) => exists,
+            Err(err) => {
+                log::error!("failed to look up room room_id = {room_id}: {err}");
+                false
+            }
+        }
     }
 
     pub async fn check_exist_user(
@@ -81,21 +214,71 @@ impl ServerApp {
             return Ok(room.check_exist_user(user_id).await);
         }
 
+        //A stored room that is not loaded has nobody in it.
+        if self.stored(room_id).await? {
+            return Ok(false);
+        }
+
         Err(ServerAppError::RoomDoesNotExist(room_id.into()))
     }
 
-    pub async fn join_room(&self, room_id: Arc<str>, user: User) -> Result<(), ServerAppError> {
+    pub async fn join_room(
+        &self,
+        room_id: Arc<str>,
+        user: User,
+        since: Sequence,
+    ) -> Result<(), ServerAppError> {
         log::info!("Join room room_id = {room_id}");
+        let _loading_lock = self.loading.lock().await;
         let mut rooms_write = self.rooms.lock().await;
 
-        let room = rooms_write.get_mut(&*room_id);
+        if !rooms_write.contains_key(&*room_id) {
+            drop(rooms_write);
+            let room = self.load_room(room_id.clone()).await?;
+            rooms_write = self.rooms.lock().await;
+            rooms_write.insert(room_id.clone(), room);
+        }
 
-        if let Some(room) = room {
-            room.join_user(user).await;
-        } else {
-            return Err(ServerAppError::RoomDoesNotExist(room_id));
+        if let Some(room) = rooms_write.get_mut(&*room_id) {
+            room.join_user(user, since).await;
         }
 
         Ok(())
     }
+
+    async fn stored(&self, room_id: &str) -> Result<bool, StorageError> {
+        let room_id = room_id.to_string();
+        storage::run_blocking(&self.storage, move |storage| storage.has_room(&room_id)).await
+    }
+
+    async fn load_room(&self, room_id: Arc<str>) -> Result<Room, ServerAppError> {
+        let now = storage::now();
+        let stored = storage::run_blocking(&self.storage, {
+            let room_id = room_id.clone();
+            move |storage| {
+                let Some(stored) = storage.load_room(&room_id)? else {
+                    return Ok(None);
+                };
+                storage.touch_room(&room_id, now)?;
+                Ok(Some(stored))
+            }
+        })
+        .await?;
+        let Some(stored) = stored else {
+            return Err(ServerAppError::RoomDoesNotExist(room_id));
+        };
+        log::info!(
+            "Load room room_id = {room_id}, created_at = {}, operations = {}",
+            stored.metadata.created_at,
+            stored.operations.len()
+        );
+
+        Ok(Room::new(
+            room_id,
+            self.sender.clone(),
+            OperationLog::from_operations(stored.operations),
+            self.storage.clone(),
+            self.heartbeat,
+        ))
+    }
 }
@@ -0,0 +1,183 @@
+use lib::operation::{LoggedOperation, Operation, RequestId, Sequence};
+
+use super::room_state::RoomState;
+
+//Rejoining clients missing more operations than this get a snapshot instead.
+const MAX_CATCH_UP: Sequence = 500;
+
+//Append-only record of every operation applied to a room.
+#[derive(Debug, Default)]
+pub struct OperationLog {
+    operations: Vec<LoggedOperation>,
+}
+
+impl OperationLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Operations must be in sequence order, as storage returns them.
+    pub fn from_operations(operations: Vec<LoggedOperation>) -> Self {
+        Self { operations }
+    }
+
+    //0 while the log is empty.
+    pub fn last_sequence(&self) -> Sequence {
+        self.operations
+            .last()
+            .map_or(0, |operation| operation.sequence)
+    }
+
+    //The operation numbered to come next. It is only part of the log once pushed.
+    pub fn next(
+        &self,
+        user_id: &str,
+        request_id: Option<RequestId>,
+        operation: Operation,
+    ) -> LoggedOperation {
+        LoggedOperation {
+            sequence: self.last_sequence() + 1,
+            user_id: user_id.to_string(),
+            request_id,
+            operation,
+        }
+    }
+
+    pub fn push(&mut self, logged: LoggedOperation) {
+        debug_assert_eq!(logged.sequence, self.last_sequence() + 1);
+        self.operations.push(logged);
+    }
+
+    //The operations after the sequence, or None when there are too many to replay
+    //or the sequence is ahead of the log.
+    pub fn since(&self, sequence: Sequence) -> Option<&[LoggedOperation]> {
+        let last_sequence = self.last_sequence();
+        if sequence > last_sequence || last_sequence - sequence > MAX_CATCH_UP {
+            return None;
+        }
+
+        let start = self
+            .operations
+            .partition_point(|operation| operation.sequence <= sequence);
+        Some(&self.operations[start..])
+    }
+
+    //Rebuilds the room by applying every operation in order.
+    pub fn replay(&self) -> RoomState {
+        let mut state = RoomState::default();
+        for logged in self.operations.iter() {
+            state.apply(&logged.operation);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::{
+        common::Color,
+        figure::{block::BlockDefinition, leaf::line::LineData, FigureData},
+    };
+
+    use super::*;
+
+    fn line(end_x: f64) -> FigureData {
+        FigureData::Line(LineData {
+            id: None,
+            start_x: 0.0,
+            start_y: 0.0,
+            end_x,
+            end_y: 1.0,
+            color: Color::new(0, 0, 0, 255),
+            stroke_style: Default::default(),
+        })
+    }
+
+    //Applies a change to the live state and logs it, the way the room does.
+    fn record(
+        state: &mut RoomState,
+        log: &mut OperationLog,
+        apply: impl FnOnce(&mut RoomState) -> Option<Operation>,
+    ) {
+        if let Some(operation) = apply(state) {
+            let logged = log.next("user", None, operation);
+            log.push(logged);
+        }
+    }
+
+    fn deletes(log: &mut OperationLog, count: usize) {
+        for _ in 0..count {
+            let logged = log.next("user", None, Operation::DeleteFigures(Vec::new()));
+            log.push(logged);
+        }
+    }
+
+    #[test]
+    fn replay_matches_the_live_state() {
+        let mut state = RoomState::default();
+        let mut log = OperationLog::new();
+        record(&mut state, &mut log, |state| {
+            Some(state.add_figures(vec![line(1.0), line(2.0), line(3.0)]))
+        });
+        let ids: Vec<_> = state
+            .figures()
+            .iter()
+            .filter_map(|data| data.id())
+            .collect();
+        record(&mut state, &mut log, |state| {
+            state.update_figures(vec![(ids[0], line(4.0))])
+        });
+        record(&mut state, &mut log, |state| {
+            state.delete_figures(vec![ids[1]])
+        });
+        //Changes nothing, so nothing is logged.
+        record(&mut state, &mut log, |state| {
+            state.delete_figures(vec![ids[1]])
+        });
+        record(&mut state, &mut log, |state| {
+            Some(state.replace_figures(vec![ids[0], ids[1]], vec![line(6.0)]))
+        });
+        record(&mut state, &mut log, |state| {
+            Some(state.define_block(BlockDefinition::new("block".to_string(), vec![line(5.0)])))
+        });
+        assert_eq!(log.last_sequence(), 5);
+        //The first figure was replaced, the already deleted second one is not listed.
+        let last = log.since(3).unwrap()[0].operation.clone();
+        let Operation::ReplaceFigures(replaced, added) = last else {
+            panic!("expected a replace, got {last:?}");
+        };
+        assert_eq!(replaced, vec![ids[0]]);
+        assert_eq!(added.len(), 1);
+
+        let replayed = log.replay();
+        assert_eq!(replayed.figures(), state.figures());
+        assert_eq!(replayed.blocks(), state.blocks());
+    }
+
+    #[test]
+    fn since_returns_the_operations_after_the_sequence() {
+        let mut log = OperationLog::new();
+        assert_eq!(log.since(0).map(<[_]>::len), Some(0));
+
+        deletes(&mut log, 5);
+        let sequences = |since| {
+            log.since(since)
+                .map(|operations| operations.iter().map(|logged| logged.sequence).collect())
+        };
+        assert_eq!(sequences(0), Some(vec![1, 2, 3, 4, 5]));
+        assert_eq!(sequences(3), Some(vec![4, 5]));
+        assert_eq!(sequences(5), Some(Vec::new()));
+        //Ahead of the log, like a client of a room that was deleted and made again.
+        assert_eq!(sequences(6), None);
+    }
+
+    #[test]
+    fn since_gives_up_past_max_catch_up() {
+        let mut log = OperationLog::new();
+        deletes(&mut log, MAX_CATCH_UP as usize + 10);
+
+        assert!(log.since(0).is_none());
+        assert!(log.since(9).is_none());
+        assert_eq!(log.since(10).map(<[_]>::len), Some(MAX_CATCH_UP as usize));
+    }
+}
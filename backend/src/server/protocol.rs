@@ -0,0 +1,297 @@
+use std::{borrow::Cow, fmt};
+
+use axum::extract::ws::{close_code, CloseFrame, Message};
+use lib::{
+    codec::{Codec, CodecError, Frame},
+    message::{
+        feature, ClientMessage, ErrorCode, ServerMessage, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    },
+};
+use tracing::log;
+
+//Rejected messages a connection may send before it is closed.
+const MAX_VIOLATIONS: u32 = 5;
+
+//How much of a rejected frame is quoted back in the error.
+const EXCERPT_LENGTH: usize = 128;
+
+const SERVER_FEATURES: &[&str] = &[feature::HEARTBEAT, feature::BINARY];
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Malformed(CodecError),
+    Unexpected(&'static str),
+    UnsupportedFrame,
+    RoomUnavailable(String),
+    HandshakeRequired,
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Malformed(err) => write!(f, "malformed message: {err}"),
+            ProtocolError::Unexpected(reason) => write!(f, "unexpected message: {reason}"),
+            ProtocolError::UnsupportedFrame => write!(f, "binary frames need the binary feature"),
+            ProtocolError::RoomUnavailable(reason) => write!(f, "room unavailable: {reason}"),
+            ProtocolError::HandshakeRequired => write!(
+                f,
+                "send Hello with protocol version {MIN_PROTOCOL_VERSION} or later first"
+            ),
+            ProtocolError::UnsupportedVersion(version) => write!(
+                f,
+                "protocol version {version} is no longer supported, \
+                version {MIN_PROTOCOL_VERSION} or later is needed"
+            ),
+        }
+    }
+}
+
+impl ProtocolError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ProtocolError::Malformed(_) => ErrorCode::MalformedMessage,
+            ProtocolError::Unexpected(_) => ErrorCode::UnexpectedMessage,
+            ProtocolError::UnsupportedFrame => ErrorCode::UnsupportedFrame,
+            ProtocolError::RoomUnavailable(_) => ErrorCode::RoomUnavailable,
+            ProtocolError::HandshakeRequired | ProtocolError::UnsupportedVersion(_) => {
+                ErrorCode::UnsupportedVersion
+            }
+        }
+    }
+
+    //in_reply_to is the rejected frame, only the start of a text one is sent back.
+    pub fn to_message(&self, in_reply_to: Option<&Frame>) -> ServerMessage {
+        let in_reply_to = match in_reply_to {
+            Some(Frame::Text(text)) => Some(excerpt(text)),
+            _ => None,
+        };
+        ServerMessage::Error {
+            code: self.code(),
+            message: self.to_string(),
+            in_reply_to,
+        }
+    }
+}
+
+//Counts the messages rejected on one connection.
+#[derive(Debug, Default)]
+pub struct Violations(u32);
+
+impl Violations {
+    //Returns true once the connection has to be closed.
+    pub fn add(&mut self) -> bool {
+        self.0 += 1;
+        self.0 >= MAX_VIOLATIONS
+    }
+}
+
+//None for frames that carry no message.
+pub fn to_frame(message: Message) -> Option<Frame> {
+    match message {
+        Message::Text(text) => Some(Frame::Text(text)),
+        Message::Binary(bytes) => Some(Frame::Binary(bytes)),
+        _ => None,
+    }
+}
+
+//Text frames are always JSON, binary ones are only taken from clients that asked for them.
+pub fn parse(codec: Codec, frame: &Frame) -> Result<ClientMessage, ProtocolError> {
+    if frame.codec() != Codec::Json && frame.codec() != codec {
+        return Err(ProtocolError::UnsupportedFrame);
+    }
+    frame.decode().map_err(ProtocolError::Malformed)
+}
+
+pub fn encode(codec: Codec, message: &ServerMessage) -> Option<Message> {
+    match codec.encode(message) {
+        Ok(Frame::Text(text)) => Some(Message::Text(text)),
+        Ok(Frame::Binary(bytes)) => Some(Message::Binary(bytes)),
+        Err(err) => {
+            log::error!("failed to encode {message:?}: {err}");
+            None
+        }
+    }
+}
+
+//Checks the first frame of a connection, which has to be a Hello in JSON whatever
+//codec the client asks for. Returns the Welcome, which lists the features both sides have.
+pub fn negotiate(frame: &Frame) -> Result<(ServerMessage, Vec<String>), ProtocolError> {
+    //Clients from before the handshake send a Join that no longer parses.
+    let message = match frame {
+        Frame::Text(_) => {
+            parse(Codec::Json, frame).map_err(|_| ProtocolError::HandshakeRequired)?
+        }
+        Frame::Binary(_) => return Err(ProtocolError::HandshakeRequired),
+    };
+    let ClientMessage::Hello {
+        protocol_version,
+        client_name,
+        capabilities,
+    } = message
+    else {
+        return Err(ProtocolError::HandshakeRequired);
+    };
+    if protocol_version < MIN_PROTOCOL_VERSION {
+        return Err(ProtocolError::UnsupportedVersion(protocol_version));
+    }
+
+    let features: Vec<String> = capabilities
+        .into_iter()
+        .filter(|capability| SERVER_FEATURES.contains(&capability.as_str()))
+        .collect();
+    log::info!(
+        "hello from {client_name} protocol_version = {protocol_version} features = {features:?}"
+    );
+
+    let welcome = ServerMessage::Welcome {
+        protocol_version: PROTOCOL_VERSION,
+        server_name: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        features: features.clone(),
+    };
+    Ok((welcome, features))
+}
+
+//Sent to a client that failed the handshake.
+pub fn handshake_close() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: close_code::PROTOCOL,
+        reason: Cow::from("unsupported protocol version"),
+    }))
+}
+
+//Sent when a connection reached MAX_VIOLATIONS.
+pub fn violation_close() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: close_code::POLICY,
+        reason: Cow::from("too many protocol violations"),
+    }))
+}
+
+fn excerpt(text: &str) -> String {
+    match text.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(codec: Codec, frame: Frame) -> ProtocolError {
+        parse(codec, &frame).expect_err("frame should be rejected")
+    }
+
+    #[test]
+    fn malformed_frames_are_rejected() {
+        for text in ["garbage", "{\"Join\":1}", "[]", "{}", "\"Shout\""] {
+            let error = rejected(Codec::Json, Frame::Text(text.to_string()));
+            assert_eq!(error.code(), ErrorCode::MalformedMessage, "{text}");
+        }
+        let error = rejected(Codec::MessagePack, Frame::Binary(vec![0xc1, 1, 2]));
+        assert_eq!(error.code(), ErrorCode::MalformedMessage);
+    }
+
+    #[test]
+    fn binary_frames_need_the_binary_codec() {
+        let Ok(Frame::Binary(bytes)) = Codec::MessagePack.encode(&ClientMessage::Leave) else {
+            panic!("MessagePack encodes to a binary frame");
+        };
+        let error = rejected(Codec::Json, Frame::Binary(bytes.clone()));
+        assert_eq!(error.code(), ErrorCode::UnsupportedFrame);
+
+        let message = parse(Codec::MessagePack, &Frame::Binary(bytes)).unwrap();
+        assert_eq!(message, ClientMessage::Leave);
+        //Text stays JSON whatever was negotiated.
+        let message = parse(Codec::MessagePack, &Frame::Text("\"Leave\"".to_string())).unwrap();
+        assert_eq!(message, ClientMessage::Leave);
+    }
+
+    #[test]
+    fn connection_closes_at_max_violations() {
+        let mut violations = Violations::default();
+        for _ in 1..MAX_VIOLATIONS {
+            assert!(!violations.add());
+        }
+        assert!(violations.add());
+    }
+
+    #[test]
+    fn error_quotes_the_start_of_the_frame() {
+        let frame = Frame::Text("x".repeat(EXCERPT_LENGTH * 2));
+        let error = rejected(Codec::Json, frame.clone());
+        let ServerMessage::Error { in_reply_to, .. } = error.to_message(Some(&frame)) else {
+            panic!("expected an error message");
+        };
+        assert_eq!(
+            in_reply_to,
+            Some(format!("{}...", "x".repeat(EXCERPT_LENGTH)))
+        );
+    }
+
+    fn hello(protocol_version: u32, capabilities: &[&str]) -> Frame {
+        let hello = ClientMessage::Hello {
+            protocol_version,
+            client_name: "test".to_string(),
+            capabilities: capabilities.iter().map(|name| name.to_string()).collect(),
+        };
+        Codec::Json.encode(&hello).unwrap()
+    }
+
+    #[test]
+    fn versions_before_the_minimum_are_rejected() {
+        let error = negotiate(&hello(MIN_PROTOCOL_VERSION - 1, &[])).unwrap_err();
+        assert!(matches!(error, ProtocolError::UnsupportedVersion(version)
+            if version == MIN_PROTOCOL_VERSION - 1));
+        assert_eq!(error.code(), ErrorCode::UnsupportedVersion);
+
+        //Newer clients are taken, the Welcome tells them what the server speaks.
+        assert!(negotiate(&hello(PROTOCOL_VERSION + 1, &[])).is_ok());
+    }
+
+    #[test]
+    fn hello_has_to_come_first() {
+        let join = ClientMessage::Join("room".to_string(), "user".to_string(), 0);
+        let frames = [
+            Codec::Json.encode(&join).unwrap(),
+            //A Join from before the handshake, without a sequence.
+            Frame::Text("{\"Join\":[\"room\",\"user\"]}".to_string()),
+            Frame::Text("garbage".to_string()),
+            //Hello in MessagePack, which is only spoken after negotiating it.
+            Codec::MessagePack
+                .encode(&ClientMessage::Hello {
+                    protocol_version: PROTOCOL_VERSION,
+                    client_name: "test".to_string(),
+                    capabilities: Vec::new(),
+                })
+                .unwrap(),
+        ];
+        for frame in frames {
+            let error = negotiate(&frame).unwrap_err();
+            assert!(
+                matches!(error, ProtocolError::HandshakeRequired),
+                "{frame:?}"
+            );
+            assert_eq!(error.code(), ErrorCode::UnsupportedVersion);
+        }
+    }
+
+    #[test]
+    fn welcome_lists_the_features_both_sides_have() {
+        let capabilities = ["binary", "compression", feature::HEARTBEAT];
+        let (welcome, features) = negotiate(&hello(PROTOCOL_VERSION, &capabilities)).unwrap();
+        assert_eq!(features, [feature::BINARY, feature::HEARTBEAT]);
+        assert_eq!(
+            welcome,
+            ServerMessage::Welcome {
+                protocol_version: PROTOCOL_VERSION,
+                server_name: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                features,
+            }
+        );
+
+        let (_, features) = negotiate(&hello(PROTOCOL_VERSION, &[])).unwrap();
+        assert!(features.is_empty());
+    }
+}
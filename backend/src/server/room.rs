@@ -1,8 +1,9 @@
 use std::{collections::HashMap, sync::Arc};
 
 use lib::{
-    figure::FigureData,
-    message::{RequestType, ResponseType, ServerMessage},
+    figure::{block::BlockDefinition, FigureData, FigureId},
+    message::{ErrorCode, RequestType, ResponseType, ServerMessage},
+    operation::{LoggedOperation, Operation, RequestId, Sequence},
 };
 use tokio::sync::{
     mpsc::{self, Receiver, Sender},
@@ -10,46 +11,106 @@ use tokio::sync::{
 };
 use tracing::log;
 
-use super::{user::User, ServerAppMessage};
+use crate::storage::{self, Storage};
+
+use super::{
+    operation_log::OperationLog, room_state::RoomState, user::User, Heartbeat, ServerAppMessage,
+};
 
 #[derive(Debug)]
 pub enum RoomMessage {
     LeaveUser(Arc<str>),
-    AddFigure(FigureData),
+    AddFigure(Arc<str>, RequestId, FigureData),
+    AddFigures(Arc<str>, RequestId, Vec<FigureData>),
+    UpdateFigure(Arc<str>, FigureId, FigureData),
+    UpdateFigures(Arc<str>, Vec<(FigureId, FigureData)>),
+    DeleteFigures(Arc<str>, Vec<FigureId>),
+    ReplaceFigures(Arc<str>, RequestId, Vec<FigureId>, Vec<FigureData>),
+    DefineBlock(Arc<str>, BlockDefinition),
     RequestInfo(Arc<str>, RequestType),
     NotifyMousePositionChanged(Arc<str>, f64, f64),
+    //Sends the user a ServerMessage::Error about a message that was not taken.
+    RejectMessage(Arc<str>, ServerMessage),
+    //Closes the socket of a user who broke the protocol too often, before LeaveUser.
+    CloseUser(Arc<str>),
 }
 
 pub struct Room {
     id: Arc<str>,
     server_app_sender: Sender<ServerAppMessage>,
     users: Arc<Mutex<HashMap<Arc<str>, User>>>,
-    figures: Arc<Mutex<Vec<FigureData>>>,
+    state: Arc<Mutex<RoomState>>,
+    log: Arc<Mutex<OperationLog>>,
+    storage: Arc<dyn Storage>,
     sender: Sender<RoomMessage>, //Pass to new_user so that room's receiver can receive a message from user.
 }
 
 impl Room {
-    pub fn new(id: Arc<str>, server_app_sender: Sender<ServerAppMessage>) -> Self {
+    //The room starts from whatever the log replays to.
+    pub fn new(
+        id: Arc<str>,
+        server_app_sender: Sender<ServerAppMessage>,
+        log: OperationLog,
+        storage: Arc<dyn Storage>,
+        heartbeat: Heartbeat,
+    ) -> Self {
         let (sender, receiver) = mpsc::channel(1000);
 
         let room = Self {
             id,
             server_app_sender,
             users: Arc::new(Mutex::new(HashMap::new())),
-            figures: Arc::new(Mutex::new(Vec::new())),
+            state: Arc::new(Mutex::new(log.replay())),
+            log: Arc::new(Mutex::new(log)),
+            storage,
             sender,
         };
 
         room.run(receiver);
+        room.run_heartbeat(heartbeat);
 
         room
     }
 
+    //Pings every user on each interval and evicts the ones that stayed silent past
+    //the timeout, which is how half-open connections get noticed. Stops with the room.
+    fn run_heartbeat(&self, heartbeat: Heartbeat) {
+        let users_clone = self.users.clone();
+        let sender_clone = self.sender.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(heartbeat.interval);
+            loop {
+                interval.tick().await;
+                if sender_clone.is_closed() {
+                    break;
+                }
+
+                let mut timed_out = Vec::new();
+                let mut users_lock = users_clone.lock().await;
+                for (user_id, user) in users_lock.iter_mut() {
+                    if user.silent_for().await > heartbeat.timeout {
+                        timed_out.push(user_id.clone());
+                    } else {
+                        user.ping(heartbeat.interval).await;
+                    }
+                }
+                drop(users_lock);
+
+                for user_id in timed_out {
+                    log::info!("heartbeat timed out user_id = {user_id}");
+                    let _ = sender_clone.send(RoomMessage::LeaveUser(user_id)).await;
+                }
+            }
+        });
+    }
+
     #[allow(clippy::single_match)]
     fn run(&self, mut receiver: Receiver<RoomMessage>) {
         let users_clone = self.users.clone();
         let server_app_sender_clone = self.server_app_sender.clone();
-        let figures_clone = self.figures.clone();
+        let state_clone = self.state.clone();
+        let log_clone = self.log.clone();
+        let storage_clone = self.storage.clone();
         let room_id = self.id.clone();
         tokio::spawn(async move {
             while let Some(message) = receiver.recv().await {
@@ -61,7 +122,7 @@ impl Room {
                         log::info!("now users = {0:?}", *users_lock);
                         if users_lock.is_empty() {
                             let _ = server_app_sender_clone
-                                .send(ServerAppMessage::DeleteRoom(room_id.clone()))
+                                .send(ServerAppMessage::UnloadRoom(room_id.clone()))
                                 .await;
                             break;
                         } else {
@@ -72,15 +133,101 @@ impl Room {
                             .await;
                         }
                     }
-                    RoomMessage::AddFigure(data) => {
-                        figures_clone.lock().await.push(data.clone());
-                        let mut users_lock = users_clone.lock().await;
-                        broadcast(&mut users_lock, ServerMessage::FigureAdded(data)).await;
+                    RoomMessage::AddFigure(user_id, request_id, data) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            Some(request_id),
+                            |state| Some(state.add_figures(vec![data])),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::AddFigures(user_id, request_id, datas) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            Some(request_id),
+                            |state| Some(state.add_figures(datas)),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::UpdateFigure(user_id, figure_id, data) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            None,
+                            |state| state.update_figures(vec![(figure_id, data)]),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::UpdateFigures(user_id, updates) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            None,
+                            |state| state.update_figures(updates),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::DeleteFigures(user_id, figure_ids) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            None,
+                            |state| state.delete_figures(figure_ids),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::ReplaceFigures(user_id, request_id, figure_ids, datas) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            Some(request_id),
+                            |state| Some(state.replace_figures(figure_ids, datas)),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
+                    }
+                    RoomMessage::DefineBlock(user_id, definition) => {
+                        let recorded = record(
+                            &state_clone,
+                            &log_clone,
+                            &storage_clone,
+                            &room_id,
+                            &user_id,
+                            None,
+                            |state| Some(state.define_block(definition)),
+                        )
+                        .await;
+                        publish(&users_clone, &user_id, recorded).await;
                     }
                     RoomMessage::RequestInfo(user_id, request_type) => match request_type {
                         RequestType::CurrentFigures => {
                             let mut users_lock = users_clone.lock().await;
-                            let vec = figures_clone.lock().await.clone();
+                            let vec = state_clone.lock().await.figures().to_vec();
                             if let Some(user) = users_lock.get_mut(&user_id) {
                                 user.send_message(ServerMessage::ResponseInfo(
                                     ResponseType::CurrentFigures(vec),
@@ -88,6 +235,22 @@ impl Room {
                                 .await;
                             }
                         }
+                        RequestType::CurrentBlocks => {
+                            let mut users_lock = users_clone.lock().await;
+                            let vec = state_clone
+                                .lock()
+                                .await
+                                .blocks()
+                                .values()
+                                .cloned()
+                                .collect();
+                            if let Some(user) = users_lock.get_mut(&user_id) {
+                                user.send_message(ServerMessage::ResponseInfo(
+                                    ResponseType::CurrentBlocks(vec),
+                                ))
+                                .await;
+                            }
+                        }
                         RequestType::CurrentSharedUsers => {
                             let mut users_lock = users_clone.lock().await;
                             let mut vec = Vec::new();
@@ -103,6 +266,19 @@ impl Room {
                         }
                         _ => {}
                     },
+                    RoomMessage::RejectMessage(user_id, error) => {
+                        let mut users_lock = users_clone.lock().await;
+                        if let Some(user) = users_lock.get_mut(&user_id) {
+                            user.send_message(error).await;
+                        }
+                    }
+                    RoomMessage::CloseUser(user_id) => {
+                        log::info!("CloseUser user_id = {user_id}");
+                        let mut users_lock = users_clone.lock().await;
+                        if let Some(user) = users_lock.get_mut(&user_id) {
+                            user.close_for_violations().await;
+                        }
+                    }
                     RoomMessage::NotifyMousePositionChanged(user_id, x, y) => {
                         let mut users_lock = users_clone.lock().await;
                         broadcast_except_for(
@@ -121,7 +297,9 @@ impl Room {
         });
     }
 
-    pub async fn join_user(&self, mut new_user: User) {
+    //since is the last sequence the user received. A user joining again under the
+    //same id, after a dropped connection, replaces the old connection.
+    pub async fn join_user(&self, mut new_user: User, since: Sequence) {
         let new_user_id = new_user.id();
         new_user.set_channel(self.sender.clone()).await;
 
@@ -133,6 +311,24 @@ impl Room {
             ServerMessage::UserJoined(new_user_id.to_string()),
         )
         .await;
+
+        //Holding the users lock, changes logged from here on are broadcast to the new
+        //user too. Ones logged just before may come twice, the client skips those.
+        let messages = self.catch_up(since).await;
+        if let Some(user) = users_lock.get_mut(&new_user_id) {
+            for message in messages {
+                user.send_message(message).await;
+            }
+        }
+    }
+
+    async fn catch_up(&self, since: Sequence) -> Vec<ServerMessage> {
+        let state_lock = self.state.lock().await;
+        let log_lock = self.log.lock().await;
+        match log_lock.since(since) {
+            Some(operations) => operations.iter().cloned().map(Into::into).collect(),
+            None => vec![snapshot(&state_lock, &log_lock)],
+        }
     }
 
     pub async fn check_exist_user(&self, user_id: &str) -> bool {
@@ -140,6 +336,78 @@ impl Room {
     }
 }
 
+//What became of a change sent to the room.
+enum Recorded {
+    //It changed nothing.
+    Unchanged,
+    Logged(LoggedOperation),
+    //Storage refused it and it was undone. The author gets this snapshot to drop it too.
+    Refused(ServerMessage),
+}
+
+//Applies a change and appends it to storage and the log. The state stays locked
+//until the log has the operation, so a snapshot never gets ahead of its sequence.
+//Storage writes on a blocking thread meanwhile, only this room waits for it.
+async fn record(
+    state: &Mutex<RoomState>,
+    log: &Mutex<OperationLog>,
+    storage: &Arc<dyn Storage>,
+    room_id: &str,
+    user_id: &str,
+    request_id: Option<RequestId>,
+    apply: impl FnOnce(&mut RoomState) -> Option<Operation>,
+) -> Recorded {
+    let mut state_lock = state.lock().await;
+    let Some(operation) = apply(&mut state_lock) else {
+        return Recorded::Unchanged;
+    };
+
+    let mut log_lock = log.lock().await;
+    let logged = log_lock.next(user_id, request_id, operation);
+    //Only stored operations are logged, so the room after a restart is the same room.
+    let stored = storage::run_blocking(storage, {
+        let room_id = room_id.to_string();
+        let logged = logged.clone();
+        move |storage| storage.append_operation(&room_id, &logged)
+    })
+    .await;
+    if let Err(err) = stored {
+        log::error!("failed to store operation room_id = {room_id}: {err}");
+        *state_lock = log_lock.replay();
+        return Recorded::Refused(snapshot(&state_lock, &log_lock));
+    }
+    log::debug!("logged operation sequence = {}", logged.sequence);
+    log_lock.push(logged.clone());
+    Recorded::Logged(logged)
+}
+
+async fn publish(users: &Mutex<HashMap<Arc<str>, User>>, user_id: &Arc<str>, recorded: Recorded) {
+    let mut users_lock = users.lock().await;
+    match recorded {
+        Recorded::Unchanged => {}
+        Recorded::Logged(logged) => broadcast(&mut users_lock, logged.into()).await,
+        Recorded::Refused(snapshot) => {
+            if let Some(user) = users_lock.get_mut(user_id) {
+                user.send_message(ServerMessage::Error {
+                    code: ErrorCode::RoomUnavailable,
+                    message: "the change could not be saved".to_string(),
+                    in_reply_to: None,
+                })
+                .await;
+                user.send_message(snapshot).await;
+            }
+        }
+    }
+}
+
+fn snapshot(state: &RoomState, log: &OperationLog) -> ServerMessage {
+    ServerMessage::Snapshot(
+        log.last_sequence(),
+        state.blocks().values().cloned().collect(),
+        state.figures().to_vec(),
+    )
+}
+
 async fn broadcast(
     users_lock: &mut MutexGuard<'_, HashMap<Arc<str>, User>>,
     message: ServerMessage,
@@ -160,3 +428,57 @@ async fn broadcast_except_for(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lib::{common::Color, figure::leaf::line::LineData};
+
+    use super::*;
+    use crate::storage::{RoomMetadata, StorageKind};
+
+    fn line() -> FigureData {
+        FigureData::Line(LineData {
+            id: None,
+            start_x: 0.0,
+            start_y: 0.0,
+            end_x: 1.0,
+            end_y: 1.0,
+            color: Color::new(0, 0, 0, 255),
+            stroke_style: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn refused_operation_is_undone() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageKind::Json.open(dir.path().to_path_buf()).unwrap();
+        let metadata = RoomMetadata {
+            id: "room".to_string(),
+            created_at: 1,
+            last_active: 1,
+        };
+        storage.create_room(&metadata).unwrap();
+        let state = Mutex::new(RoomState::default());
+        let log = Mutex::new(OperationLog::new());
+
+        let recorded = record(&state, &log, &storage, "room", "user", None, |state| {
+            Some(state.add_figures(vec![line()]))
+        })
+        .await;
+        assert!(matches!(recorded, Recorded::Logged(ref logged) if logged.sequence == 1));
+
+        //Without its directory the room cannot take more operations.
+        storage.delete_room("room").unwrap();
+        let recorded = record(&state, &log, &storage, "room", "user", None, |state| {
+            Some(state.add_figures(vec![line(), line()]))
+        })
+        .await;
+        let Recorded::Refused(ServerMessage::Snapshot(sequence, _, figures)) = recorded else {
+            panic!("operation was not refused");
+        };
+        assert_eq!(sequence, 1);
+        assert_eq!(figures.len(), 1);
+        assert_eq!(state.lock().await.figures().len(), 1);
+        assert_eq!(log.lock().await.last_sequence(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use lib::{
+    figure::{
+        block::{BlockDefinition, BlockName},
+        FigureData, FigureId,
+    },
+    operation::Operation,
+};
+use uuid::Uuid;
+
+//Figures and blocks of a room. Every change goes through apply, so the state
+//built by handling requests is the same as the one built by replaying the log.
+#[derive(Debug, Default)]
+pub struct RoomState {
+    figures: Vec<FigureData>,
+    blocks: HashMap<BlockName, BlockDefinition>,
+}
+
+impl RoomState {
+    pub fn figures(&self) -> &[FigureData] {
+        &self.figures
+    }
+
+    pub fn blocks(&self) -> &HashMap<BlockName, BlockDefinition> {
+        &self.blocks
+    }
+
+    //Whatever ids the client sent are replaced, ids are only handed out here.
+    pub fn add_figures(&mut self, datas: Vec<FigureData>) -> Operation {
+        let operation = Operation::AddFigures(with_new_ids(datas));
+        self.apply(&operation);
+        operation
+    }
+
+    //Updates of figures that no longer exist are dropped. None when nothing is left.
+    pub fn update_figures(&mut self, updates: Vec<(FigureId, FigureData)>) -> Option<Operation> {
+        let updates: Vec<(FigureId, FigureData)> = updates
+            .into_iter()
+            .filter(|(figure_id, _)| self.contains(*figure_id))
+            .map(|(figure_id, mut data)| {
+                data.set_id(figure_id);
+                (figure_id, data)
+            })
+            .collect();
+        if updates.is_empty() {
+            return None;
+        }
+
+        let operation = Operation::UpdateFigures(updates);
+        self.apply(&operation);
+        Some(operation)
+    }
+
+    //Keeps only the ids that exist, once each. None when there are none.
+    pub fn delete_figures(&mut self, figure_ids: Vec<FigureId>) -> Option<Operation> {
+        let figure_ids = self.existing(figure_ids);
+        if figure_ids.is_empty() {
+            return None;
+        }
+
+        let operation = Operation::DeleteFigures(figure_ids);
+        self.apply(&operation);
+        Some(operation)
+    }
+
+    //Like delete_figures followed by add_figures, but always an operation since the
+    //sender waits for the echo of its new figures.
+    pub fn replace_figures(
+        &mut self,
+        figure_ids: Vec<FigureId>,
+        datas: Vec<FigureData>,
+    ) -> Operation {
+        let operation = Operation::ReplaceFigures(self.existing(figure_ids), with_new_ids(datas));
+        self.apply(&operation);
+        operation
+    }
+
+    pub fn define_block(&mut self, definition: BlockDefinition) -> Operation {
+        let operation = Operation::DefineBlock(definition);
+        self.apply(&operation);
+        operation
+    }
+
+    pub fn apply(&mut self, operation: &Operation) {
+        match operation {
+            Operation::AddFigures(datas) => self.figures.extend(datas.iter().cloned()),
+            Operation::UpdateFigures(updates) => {
+                for (figure_id, data) in updates {
+                    if let Some(figure) = self
+                        .figures
+                        .iter_mut()
+                        .find(|figure| figure.id() == Some(*figure_id))
+                    {
+                        *figure = data.clone();
+                    }
+                }
+            }
+            Operation::DeleteFigures(figure_ids) => self.remove(figure_ids),
+            Operation::ReplaceFigures(figure_ids, datas) => {
+                self.remove(figure_ids);
+                self.figures.extend(datas.iter().cloned());
+            }
+            Operation::DefineBlock(definition) => {
+                self.blocks
+                    .insert(definition.name.clone(), definition.clone());
+            }
+        }
+    }
+
+    fn contains(&self, figure_id: FigureId) -> bool {
+        self.figures
+            .iter()
+            .any(|figure| figure.id() == Some(figure_id))
+    }
+
+    //The ids that exist, once each.
+    fn existing(&self, figure_ids: Vec<FigureId>) -> Vec<FigureId> {
+        let mut existing: Vec<FigureId> = Vec::new();
+        for figure_id in figure_ids {
+            if self.contains(figure_id) && !existing.contains(&figure_id) {
+                existing.push(figure_id);
+            }
+        }
+        existing
+    }
+
+    fn remove(&mut self, figure_ids: &[FigureId]) {
+        self.figures.retain(|figure| match figure.id() {
+            Some(id) => !figure_ids.contains(&id),
+            None => true,
+        });
+    }
+}
+
+fn with_new_ids(datas: Vec<FigureData>) -> Vec<FigureData> {
+    datas
+        .into_iter()
+        .map(|mut data| {
+            data.set_id(Uuid::new_v4());
+            data
+        })
+        .collect()
+}
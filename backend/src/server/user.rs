@@ -1,21 +1,44 @@
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::extract::ws::{Message, WebSocket};
 use futures::{
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
-use lib::message::{ClientMessage, ServerMessage};
-use tokio::sync::{mpsc::Sender, Mutex};
+use lib::{
+    codec::Codec,
+    message::{feature, ClientMessage, ServerMessage},
+};
+use tokio::{
+    sync::{mpsc::Sender, Mutex},
+    task::JoinHandle,
+};
 use tracing::log::{self};
 
-use super::room::RoomMessage;
+use super::{
+    protocol::{self, ProtocolError, Violations},
+    room::RoomMessage,
+};
+
+//A send that takes longer than this is given up, so one stuck connection cannot
+//hold up a broadcast to the whole room.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct User {
     id: Arc<str>,
     room_sender: Arc<Mutex<Option<Sender<RoomMessage>>>>,
     socket_sender: SplitSink<WebSocket, Message>,
     socket_receiver: Option<SplitStream<WebSocket>>,
+    //When anything last came in from the socket.
+    last_seen: Arc<Mutex<Instant>>,
+    receive_task: Option<JoinHandle<()>>,
+    //Negotiated in the handshake.
+    features: Vec<String>,
+    codec: Codec,
 }
 
 impl fmt::Debug for User {
@@ -29,12 +52,17 @@ impl User {
         id: Arc<str>,
         sender: SplitSink<WebSocket, Message>,
         receiver: SplitStream<WebSocket>,
+        features: Vec<String>,
     ) -> Self {
         Self {
             id,
             room_sender: Arc::new(Mutex::new(None)),
             socket_sender: sender,
             socket_receiver: Some(receiver),
+            last_seen: Arc::new(Mutex::new(Instant::now())),
+            receive_task: None,
+            codec: Codec::negotiated(&features),
+            features,
         }
     }
 
@@ -44,11 +72,23 @@ impl User {
         *self.room_sender.lock().await = Some(room_sender.clone());
         let room_sender_clone = self.room_sender.clone();
 
-        let socket_receiver = self.socket_receiver.take().unwrap();
+        let Some(socket_receiver) = self.socket_receiver.take() else {
+            log::error!("socket is already being read user_id = {}", self.id);
+            return;
+        };
+        let last_seen_clone = self.last_seen.clone();
+        let codec = self.codec;
 
-        tokio::spawn(async move {
-            handle_message(id, room_sender_clone, socket_receiver).await;
-        });
+        self.receive_task = Some(tokio::spawn(async move {
+            handle_message(
+                id,
+                room_sender_clone,
+                socket_receiver,
+                last_seen_clone,
+                codec,
+            )
+            .await;
+        }));
     }
 
     pub fn id(&self) -> Arc<str> {
@@ -56,8 +96,47 @@ impl User {
     }
 
     pub async fn send_message(&mut self, message: ServerMessage) {
-        let message = serde_json::to_string(&message).unwrap();
-        let _ = self.socket_sender.send(Message::Text(message)).await;
+        if let Some(message) = protocol::encode(self.codec, &message) {
+            self.send(message).await;
+        }
+    }
+
+    //Tells the client why the connection ends. Leaving the room closes it.
+    pub async fn close_for_violations(&mut self) {
+        self.send(protocol::violation_close()).await;
+    }
+
+    async fn send(&mut self, message: Message) {
+        let sent = tokio::time::timeout(SEND_TIMEOUT, self.socket_sender.send(message));
+        if sent.await.is_err() {
+            log::warn!("send timed out user_id = {}", self.id);
+        }
+    }
+
+    //A heartbeat the client's protocol understands. Browsers answer websocket pings on
+    //their own, which is enough to tell the connection is alive.
+    pub async fn ping(&mut self, interval: Duration) {
+        if self.features.iter().any(|name| name == feature::HEARTBEAT) {
+            self.send_message(ServerMessage::Ping(interval.as_millis() as u64))
+                .await;
+        } else {
+            self.send(Message::Ping(Vec::new())).await;
+        }
+    }
+
+    pub async fn silent_for(&self) -> Duration {
+        self.last_seen.lock().await.elapsed()
+    }
+}
+
+//Stops reading the socket once the user is out of the room, whether it left,
+//timed out or was replaced by a new connection under the same id. Otherwise a
+//late close on the old socket would make the new one leave.
+impl Drop for User {
+    fn drop(&mut self) {
+        if let Some(receive_task) = self.receive_task.take() {
+            receive_task.abort();
+        }
     }
 }
 
@@ -65,48 +144,108 @@ async fn handle_message(
     id: Arc<str>,
     room_sender: Arc<Mutex<Option<Sender<RoomMessage>>>>,
     mut socket_receiver: SplitStream<WebSocket>,
+    last_seen: Arc<Mutex<Instant>>,
+    codec: Codec,
 ) {
-    let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(message)) = socket_receiver.next().await {
-            if let Message::Text(message) = message {
-                let message: ClientMessage = serde_json::from_str(&message).unwrap();
-                let room_message = match message {
-                    ClientMessage::Leave => {
-                        let sender_lock = room_sender.lock().await;
-                        if let Some(sender) = &*sender_lock {
-                            sender.send(RoomMessage::LeaveUser(id)).await.unwrap();
-                        }
-                        break;
-                    }
-                    ClientMessage::AddFigure(data) => RoomMessage::AddFigure(data),
-                    ClientMessage::RequestInfo(request_type) => {
-                        RoomMessage::RequestInfo(id.clone(), request_type)
-                    }
-                    ClientMessage::NotifyMousePositionChanged(x, y) => {
-                        RoomMessage::NotifyMousePositionChanged(id.clone(), x, y)
-                    }
-                    _ => {
-                        continue;
-                    }
-                };
-
-                let sender_lock = room_sender.lock().await;
-                if let Some(sender) = &*sender_lock {
-                    sender.send(room_message).await.unwrap();
-                }
-            } else if let Message::Close(_) = message {
-                let sender_lock = room_sender.lock().await;
-                if let Some(sender) = &*sender_lock {
-                    sender.send(RoomMessage::LeaveUser(id)).await.unwrap();
-                }
+    let mut violations = Violations::default();
+    loop {
+        let message = match socket_receiver.next().await {
+            Some(Ok(message)) => message,
+            Some(Err(err)) => {
+                log::info!("socket error user_id = {id}: {err}");
                 break;
-            } else {
-                log::info!("other message {message:?}");
+            }
+            None => break,
+        };
+        *last_seen.lock().await = Instant::now();
+        let result = match message {
+            Message::Close(_) => Ok(Some(RoomMessage::LeaveUser(id.clone()))),
+            message => match protocol::to_frame(message) {
+                Some(frame) => protocol::parse(codec, &frame)
+                    .and_then(|message| to_room_message(&id, message))
+                    .map_err(|error| (error, Some(frame))),
+                None => Ok(None),
+            },
+        };
+
+        let mut room_messages = Vec::new();
+        let mut leaving = false;
+        match result {
+            Ok(Some(room_message)) => {
+                leaving = matches!(room_message, RoomMessage::LeaveUser(_));
+                room_messages.push(room_message);
+            }
+            Ok(None) => continue,
+            Err((error, in_reply_to)) => {
+                log::warn!("rejected message user_id = {id}: {error}");
+                room_messages.push(RoomMessage::RejectMessage(
+                    id.clone(),
+                    error.to_message(in_reply_to.as_ref()),
+                ));
+                if violations.add() {
+                    room_messages.push(RoomMessage::CloseUser(id.clone()));
+                    room_messages.push(RoomMessage::LeaveUser(id.clone()));
+                    leaving = true;
+                }
             }
         }
-    });
 
-    tokio::select! {
-        _ = (&mut recv_task) => {}
+        let sender_lock = room_sender.lock().await;
+        let Some(sender) = &*sender_lock else {
+            return;
+        };
+        for room_message in room_messages {
+            if sender.send(room_message).await.is_err() {
+                log::info!("room is gone user_id = {id}");
+                return;
+            }
+        }
+        if leaving {
+            return;
+        }
+    }
+
+    //The socket ended without a goodbye. A connection replaced by a new one never
+    //gets here, its task is aborted first.
+    if let Some(sender) = &*room_sender.lock().await {
+        let _ = sender.send(RoomMessage::LeaveUser(id.clone())).await;
+    }
+}
+
+//None for messages that only keep the connection alive.
+fn to_room_message(
+    id: &Arc<str>,
+    message: ClientMessage,
+) -> Result<Option<RoomMessage>, ProtocolError> {
+    let room_message = match message {
+        ClientMessage::Leave => RoomMessage::LeaveUser(id.clone()),
+        ClientMessage::Hello { .. } | ClientMessage::Join(..) => {
+            return Err(ProtocolError::Unexpected("already joined"))
+        }
+        ClientMessage::Pong => return Ok(None),
+        ClientMessage::AddFigure(request_id, data) => {
+            RoomMessage::AddFigure(id.clone(), request_id, data)
+        }
+        ClientMessage::AddFigures(request_id, datas) => {
+            RoomMessage::AddFigures(id.clone(), request_id, datas)
+        }
+        ClientMessage::UpdateFigure(figure_id, data) => {
+            RoomMessage::UpdateFigure(id.clone(), figure_id, data)
+        }
+        ClientMessage::UpdateFigures(updates) => RoomMessage::UpdateFigures(id.clone(), updates),
+        ClientMessage::DeleteFigures(figure_ids) => {
+            RoomMessage::DeleteFigures(id.clone(), figure_ids)
+        }
+        ClientMessage::ReplaceFigures(request_id, figure_ids, datas) => {
+            RoomMessage::ReplaceFigures(id.clone(), request_id, figure_ids, datas)
+        }
+        ClientMessage::DefineBlock(definition) => RoomMessage::DefineBlock(id.clone(), definition),
+        ClientMessage::RequestInfo(request_type) => {
+            RoomMessage::RequestInfo(id.clone(), request_type)
+        }
+        ClientMessage::NotifyMousePositionChanged(x, y) => {
+            RoomMessage::NotifyMousePositionChanged(id.clone(), x, y)
+        }
     };
+    Ok(Some(room_message))
 }
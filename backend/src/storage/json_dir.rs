@@ -0,0 +1,231 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use lib::operation::LoggedOperation;
+use tracing::log;
+
+use super::{RoomMetadata, Storage, StorageError, StoredRoom};
+
+const METADATA_FILE: &str = "room.json";
+const OPERATIONS_FILE: &str = "operations.jsonl";
+
+//Every room is a directory under the root, named after the hex encoded room id
+//so that any id is a valid file name. It holds the metadata as JSON and the
+//operations as one JSON object per line, appended as they happen.
+pub struct JsonDirStorage {
+    root: PathBuf,
+}
+
+impl JsonDirStorage {
+    pub fn open(root: PathBuf) -> Result<Self, StorageError> {
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn room_dir(&self, room_id: &str) -> PathBuf {
+        let name: String = room_id.bytes().map(|byte| format!("{byte:02x}")).collect();
+        self.root.join(name)
+    }
+
+    fn read_metadata(&self, room_id: &str) -> Result<Option<RoomMetadata>, StorageError> {
+        match fs::read_to_string(self.room_dir(room_id).join(METADATA_FILE)) {
+            Ok(text) => Ok(Some(serde_json::from_str(&text)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write_metadata(&self, metadata: &RoomMetadata) -> Result<(), StorageError> {
+        let dir = self.room_dir(&metadata.id);
+        fs::create_dir_all(&dir)?;
+        //Written aside and renamed so a crash never leaves half a file.
+        let temporary = dir.join(format!("{METADATA_FILE}.tmp"));
+        fs::write(&temporary, serde_json::to_string(metadata)?)?;
+        fs::rename(temporary, dir.join(METADATA_FILE))?;
+        Ok(())
+    }
+}
+
+impl Storage for JsonDirStorage {
+    fn has_room(&self, room_id: &str) -> Result<bool, StorageError> {
+        Ok(self.read_metadata(room_id)?.is_some())
+    }
+
+    fn create_room(&self, metadata: &RoomMetadata) -> Result<(), StorageError> {
+        let operations = self.room_dir(&metadata.id).join(OPERATIONS_FILE);
+        if operations.exists() {
+            fs::remove_file(operations)?;
+        }
+        self.write_metadata(metadata)
+    }
+
+    fn load_room(&self, room_id: &str) -> Result<Option<StoredRoom>, StorageError> {
+        let Some(metadata) = self.read_metadata(room_id)? else {
+            return Ok(None);
+        };
+
+        let path = self.room_dir(room_id).join(OPERATIONS_FILE);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut operations = Vec::new();
+        let mut start = 0;
+        for line in bytes.split_inclusive(|byte| *byte == b'\n') {
+            let end = start + line.len();
+            if !line.trim_ascii().is_empty() {
+                match serde_json::from_slice(line) {
+                    Ok(operation) => operations.push(operation),
+                    //A crash in append_operation can only cut off the last line. It is
+                    //cut from the file too, so the next append starts on a fresh line.
+                    Err(err) if end == bytes.len() => {
+                        log::warn!(
+                            "dropping cut off operation room_id = {room_id} \
+                            sequence = {}: {err}",
+                            operations.len() + 1
+                        );
+                        OpenOptions::new()
+                            .write(true)
+                            .open(&path)?
+                            .set_len(start as u64)?;
+                        break;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+                if !line.ends_with(b"\n") {
+                    OpenOptions::new()
+                        .append(true)
+                        .open(&path)?
+                        .write_all(b"\n")?;
+                }
+            }
+            start = end;
+        }
+
+        Ok(Some(StoredRoom {
+            metadata,
+            operations,
+        }))
+    }
+
+    fn append_operation(
+        &self,
+        room_id: &str,
+        operation: &LoggedOperation,
+    ) -> Result<(), StorageError> {
+        let mut line = serde_json::to_string(operation)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.room_dir(room_id).join(OPERATIONS_FILE))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn touch_room(&self, room_id: &str, last_active: u64) -> Result<(), StorageError> {
+        if let Some(mut metadata) = self.read_metadata(room_id)? {
+            metadata.last_active = last_active;
+            self.write_metadata(&metadata)?;
+        }
+        Ok(())
+    }
+
+    fn inactive_rooms(&self, before: u64) -> Result<Vec<String>, StorageError> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path().join(METADATA_FILE);
+            let Ok(text) = fs::read_to_string(path) else {
+                continue;
+            };
+            let metadata: RoomMetadata = serde_json::from_str(&text)?;
+            if metadata.last_active < before {
+                ids.push(metadata.id);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn delete_room(&self, room_id: &str) -> Result<(), StorageError> {
+        match fs::remove_dir_all(self.room_dir(room_id)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::operation::Operation;
+
+    use super::*;
+
+    fn logged(sequence: u64) -> LoggedOperation {
+        LoggedOperation {
+            sequence,
+            user_id: "user".to_string(),
+            request_id: None,
+            operation: Operation::DeleteFigures(Vec::new()),
+        }
+    }
+
+    fn open_room() -> (tempfile::TempDir, JsonDirStorage, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = JsonDirStorage::open(dir.path().to_path_buf()).unwrap();
+        let metadata = RoomMetadata {
+            id: "room".to_string(),
+            created_at: 1,
+            last_active: 1,
+        };
+        storage.create_room(&metadata).unwrap();
+        storage.append_operation("room", &logged(1)).unwrap();
+        storage.append_operation("room", &logged(2)).unwrap();
+        let path = storage.room_dir("room").join(OPERATIONS_FILE);
+        (dir, storage, path)
+    }
+
+    fn sequences(storage: &JsonDirStorage) -> Vec<u64> {
+        let room = storage.load_room("room").unwrap().unwrap();
+        room.operations
+            .iter()
+            .map(|logged| logged.sequence)
+            .collect()
+    }
+
+    #[test]
+    fn cut_off_last_line_is_dropped() {
+        let (_dir, storage, path) = open_room();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"sequence\":3,\"user_i").unwrap();
+
+        assert_eq!(sequences(&storage), vec![1, 2]);
+        storage.append_operation("room", &logged(3)).unwrap();
+        assert_eq!(sequences(&storage), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn last_line_without_newline_is_kept() {
+        let (_dir, storage, path) = open_room();
+        let mut text = fs::read_to_string(&path).unwrap();
+        text.pop();
+        fs::write(&path, text).unwrap();
+
+        assert_eq!(sequences(&storage), vec![1, 2]);
+        storage.append_operation("room", &logged(3)).unwrap();
+        assert_eq!(sequences(&storage), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn bad_line_in_the_middle_is_an_error() {
+        let (_dir, storage, path) = open_room();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::write(&path, format!("garbage\n{text}")).unwrap();
+
+        assert!(storage.load_room("room").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    fmt, io,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use lib::operation::LoggedOperation;
+use serde::{Deserialize, Serialize};
+
+use self::{json_dir::JsonDirStorage, sqlite::SqliteStorage};
+
+pub mod json_dir;
+pub mod sqlite;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    //One directory per room holding JSON files.
+    Json,
+    //A single SQLite database file.
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn default_path(&self) -> PathBuf {
+        match self {
+            StorageKind::Json => PathBuf::from("rooms"),
+            StorageKind::Sqlite => PathBuf::from("rooms.sqlite3"),
+        }
+    }
+
+    pub fn open(&self, path: PathBuf) -> Result<Arc<dyn Storage>, StorageError> {
+        Ok(match self {
+            StorageKind::Json => Arc::new(JsonDirStorage::open(path)?),
+            StorageKind::Sqlite => Arc::new(SqliteStorage::open(path)?),
+        })
+    }
+}
+
+//Times are seconds since the unix epoch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoomMetadata {
+    pub id: String,
+    pub created_at: u64,
+    //When the last user left, or when the room was created or loaded.
+    pub last_active: u64,
+}
+
+impl RoomMetadata {
+    pub fn new(id: &str) -> Self {
+        let now = now();
+        Self {
+            id: id.to_string(),
+            created_at: now,
+            last_active: now,
+        }
+    }
+}
+
+pub struct StoredRoom {
+    pub metadata: RoomMetadata,
+    //In sequence order.
+    pub operations: Vec<LoggedOperation>,
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Sqlite(rusqlite::Error),
+    //The blocking task running the call panicked or was cancelled.
+    Task(tokio::task::JoinError),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(err) => write!(f, "storage io error: {err}"),
+            StorageError::Json(err) => write!(f, "storage json error: {err}"),
+            StorageError::Sqlite(err) => write!(f, "storage sqlite error: {err}"),
+            StorageError::Task(err) => write!(f, "storage task failed: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        StorageError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        StorageError::Json(err)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        StorageError::Sqlite(err)
+    }
+}
+
+impl From<tokio::task::JoinError> for StorageError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        StorageError::Task(err)
+    }
+}
+
+//Where rooms outlive their users and the process. A room is its metadata plus
+//its operation log, replaying the log rebuilds the figures.
+//Calls block on file or database I/O, async code makes them through run_blocking.
+pub trait Storage: Send + Sync {
+    fn has_room(&self, room_id: &str) -> Result<bool, StorageError>;
+
+    fn create_room(&self, metadata: &RoomMetadata) -> Result<(), StorageError>;
+
+    fn load_room(&self, room_id: &str) -> Result<Option<StoredRoom>, StorageError>;
+
+    fn append_operation(
+        &self,
+        room_id: &str,
+        operation: &LoggedOperation,
+    ) -> Result<(), StorageError>;
+
+    fn touch_room(&self, room_id: &str, last_active: u64) -> Result<(), StorageError>;
+
+    //Ids of the rooms whose last activity is older than the given time.
+    fn inactive_rooms(&self, before: u64) -> Result<Vec<String>, StorageError>;
+
+    fn delete_room(&self, room_id: &str) -> Result<(), StorageError>;
+}
+
+//Runs a storage call on tokio's blocking threads, so a slow disk holds up only the
+//task waiting for the result and not the runtime's worker threads.
+pub async fn run_blocking<T, F>(storage: &Arc<dyn Storage>, call: F) -> Result<T, StorageError>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn Storage) -> Result<T, StorageError> + Send + 'static,
+{
+    let storage = storage.clone();
+    tokio::task::spawn_blocking(move || call(&*storage)).await?
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::operation::Operation;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn metadata(id: &str, last_active: u64) -> RoomMetadata {
+        RoomMetadata {
+            id: id.to_string(),
+            created_at: 10,
+            last_active,
+        }
+    }
+
+    fn logged(sequence: u64) -> LoggedOperation {
+        LoggedOperation {
+            sequence,
+            user_id: "user".to_string(),
+            request_id: Some(sequence),
+            operation: Operation::DeleteFigures(vec![Uuid::new_v4()]),
+        }
+    }
+
+    fn round_trip(storage: &dyn Storage) {
+        //Ids that are no valid file names have to work too.
+        let room_id = "room/../ä";
+        assert!(!storage.has_room(room_id).unwrap());
+        assert!(storage.load_room(room_id).unwrap().is_none());
+
+        storage.create_room(&metadata(room_id, 100)).unwrap();
+        storage.create_room(&metadata("other", 300)).unwrap();
+        assert!(storage.has_room(room_id).unwrap());
+
+        let operations = vec![logged(1), logged(2), logged(3)];
+        for operation in operations.iter() {
+            storage.append_operation(room_id, operation).unwrap();
+        }
+        let room = storage.load_room(room_id).unwrap().unwrap();
+        assert_eq!(room.metadata, metadata(room_id, 100));
+        assert_eq!(room.operations, operations);
+        assert!(storage
+            .load_room("other")
+            .unwrap()
+            .unwrap()
+            .operations
+            .is_empty());
+
+        assert_eq!(
+            storage.inactive_rooms(200).unwrap(),
+            vec![room_id.to_string()]
+        );
+        storage.touch_room(room_id, 400).unwrap();
+        let room = storage.load_room(room_id).unwrap().unwrap();
+        assert_eq!(room.metadata.last_active, 400);
+        assert_eq!(room.metadata.created_at, 10);
+        assert_eq!(
+            storage.inactive_rooms(350).unwrap(),
+            vec!["other".to_string()]
+        );
+
+        storage.delete_room(room_id).unwrap();
+        assert!(!storage.has_room(room_id).unwrap());
+        assert!(storage.load_room(room_id).unwrap().is_none());
+        assert!(storage.has_room("other").unwrap());
+        //Deleting twice is fine.
+        storage.delete_room(room_id).unwrap();
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageKind::Json.open(dir.path().join("rooms")).unwrap();
+        round_trip(&*storage);
+    }
+
+    #[test]
+    fn sqlite_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageKind::Sqlite
+            .open(dir.path().join("rooms.sqlite3"))
+            .unwrap();
+        round_trip(&*storage);
+    }
+
+    #[test]
+    fn sqlite_deletes_operations_with_the_room() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageKind::Sqlite
+            .open(dir.path().join("rooms.sqlite3"))
+            .unwrap();
+        storage.create_room(&metadata("room", 1)).unwrap();
+        storage.append_operation("room", &logged(1)).unwrap();
+        storage.delete_room("room").unwrap();
+        storage.create_room(&metadata("room", 1)).unwrap();
+        assert!(storage
+            .load_room("room")
+            .unwrap()
+            .unwrap()
+            .operations
+            .is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use lib::operation::LoggedOperation;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{RoomMetadata, Storage, StorageError, StoredRoom};
+
+//Operations are stored as JSON text, keyed by room and sequence.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: PathBuf) -> Result<Self, StorageError> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA foreign_keys = ON;
+            CREATE TABLE IF NOT EXISTS rooms (
+                id TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                last_active INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS operations (
+                room_id TEXT NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+                sequence INTEGER NOT NULL,
+                user_id TEXT NOT NULL,
+                request_id INTEGER,
+                operation TEXT NOT NULL,
+                PRIMARY KEY (room_id, sequence)
+            );",
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        //A panic while holding the lock leaves the connection itself usable.
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn has_room(&self, room_id: &str) -> Result<bool, StorageError> {
+        let found = self
+            .connection()
+            .query_row("SELECT 1 FROM rooms WHERE id = ?1", [room_id], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    fn create_room(&self, metadata: &RoomMetadata) -> Result<(), StorageError> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO rooms (id, created_at, last_active) VALUES (?1, ?2, ?3)",
+            params![metadata.id, metadata.created_at, metadata.last_active],
+        )?;
+        Ok(())
+    }
+
+    fn load_room(&self, room_id: &str) -> Result<Option<StoredRoom>, StorageError> {
+        let connection = self.connection();
+        let metadata = connection
+            .query_row(
+                "SELECT id, created_at, last_active FROM rooms WHERE id = ?1",
+                [room_id],
+                |row| {
+                    Ok(RoomMetadata {
+                        id: row.get(0)?,
+                        created_at: row.get(1)?,
+                        last_active: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        let Some(metadata) = metadata else {
+            return Ok(None);
+        };
+
+        let mut statement = connection.prepare(
+            "SELECT sequence, user_id, request_id, operation FROM operations
+            WHERE room_id = ?1 ORDER BY sequence",
+        )?;
+        let rows = statement.query_map([room_id], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<u64>>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut operations = Vec::new();
+        for row in rows {
+            let (sequence, user_id, request_id, operation) = row?;
+            operations.push(LoggedOperation {
+                sequence,
+                user_id,
+                request_id,
+                operation: serde_json::from_str(&operation)?,
+            });
+        }
+
+        Ok(Some(StoredRoom {
+            metadata,
+            operations,
+        }))
+    }
+
+    fn append_operation(
+        &self,
+        room_id: &str,
+        operation: &LoggedOperation,
+    ) -> Result<(), StorageError> {
+        self.connection().execute(
+            "INSERT INTO operations (room_id, sequence, user_id, request_id, operation)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                room_id,
+                operation.sequence,
+                operation.user_id,
+                operation.request_id,
+                serde_json::to_string(&operation.operation)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn touch_room(&self, room_id: &str, last_active: u64) -> Result<(), StorageError> {
+        self.connection().execute(
+            "UPDATE rooms SET last_active = ?2 WHERE id = ?1",
+            params![room_id, last_active],
+        )?;
+        Ok(())
+    }
+
+    fn inactive_rooms(&self, before: u64) -> Result<Vec<String>, StorageError> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT id FROM rooms WHERE last_active < ?1")?;
+        let ids = statement
+            .query_map([before], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(ids)
+    }
+
+    fn delete_room(&self, room_id: &str) -> Result<(), StorageError> {
+        self.connection()
+            .execute("DELETE FROM rooms WHERE id = ?1", [room_id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use lib::{
+    common::{Color, StrokeStyle},
+    figure::{
+        leaf::{
+            bezier::{Bezier, BezierSegment},
+            line::Line,
+        },
+        Figure,
+    },
+};
+use web_sys::KeyboardEvent;
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{make_handle, DrawMode, ShouldAction};
+
+//Each click places an anchor. Dragging before releasing the button pulls out the anchor's
+//control handles, keeping the curve smooth through it.
+//Finishes on double click or Enter, Esc throws the curve away.
+#[derive(Default)]
+pub struct BezierMode {
+    start: Option<(f64, f64)>,
+    segments: Vec<BezierSegment>,
+    //Outgoing control point of the last anchor, used as control1 of the next segment.
+    out_handle: (f64, f64),
+    dragging: bool,
+}
+
+impl BezierMode {
+    pub fn new() -> Self {
+        BezierMode {
+            start: None,
+            segments: Vec::new(),
+            out_handle: (0.0, 0.0),
+            dragging: false,
+        }
+    }
+
+    fn last_anchor(&self) -> Option<(f64, f64)> {
+        match self.segments.last() {
+            Some(segment) => Some(segment.end),
+            None => self.start,
+        }
+    }
+
+    fn finish(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let start = self.start.take();
+        let segments = std::mem::take(&mut self.segments);
+        self.dragging = false;
+        data.take_preview();
+        data.take_guides();
+
+        match start {
+            Some(start) if !segments.is_empty() => {
+                let bezier = Bezier::new(
+                    start,
+                    segments,
+                    Color::new(0, 0, 0, 255),
+                    StrokeStyle::default(),
+                );
+                Some(ShouldAction::AddFigure(Box::new(bezier)))
+            }
+            _ => Some(ShouldAction::Rerender(DrawOption::DrawAll)),
+        }
+    }
+
+    fn update_preview(&self, x: f64, y: f64, data: &mut DrawAreaData) {
+        let (Some(start), Some(anchor)) = (self.start, self.last_anchor()) else {
+            return;
+        };
+
+        let mut bezier = Bezier::new(
+            start,
+            self.segments.clone(),
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        );
+        if !self.dragging {
+            bezier.push_segment(BezierSegment::new(self.out_handle, (x, y), (x, y)));
+        }
+        data.set_preview(Some(Box::new(bezier)));
+
+        let mut guides: Vec<Box<dyn Figure>> = Vec::new();
+        let mut handles = vec![self.out_handle];
+        if let Some(segment) = self.segments.last() {
+            handles.push(segment.control2);
+        }
+        for (handle_x, handle_y) in handles {
+            if (handle_x, handle_y) != anchor {
+                let color = Color::new(0, 149, 255, 255);
+                guides.push(Box::new(Line::new(
+                    anchor.0,
+                    anchor.1,
+                    handle_x,
+                    handle_y,
+                    color,
+                    StrokeStyle::default(),
+                )));
+                guides.push(make_handle(handle_x, handle_y, data));
+            }
+        }
+        guides.push(make_handle(anchor.0, anchor.1, data));
+        data.set_guides(guides);
+    }
+}
+
+impl DrawMode for BezierMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        //The first click of a double click already placed the last anchor.
+        if event.detail() >= 2 {
+            return self.finish(data);
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if self.start.is_none() {
+            self.start = Some((x, y));
+        } else {
+            self.segments
+                .push(BezierSegment::new(self.out_handle, (x, y), (x, y)));
+        }
+        self.out_handle = (x, y);
+        self.dragging = true;
+
+        self.update_preview(x, y, data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let anchor = self.last_anchor()?;
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if self.dragging {
+            //The incoming handle mirrors the outgoing one so the curve stays smooth.
+            self.out_handle = (x, y);
+            if let Some(segment) = self.segments.last_mut() {
+                segment.control2 = (2.0 * anchor.0 - x, 2.0 * anchor.1 - y);
+            }
+        }
+
+        self.update_preview(x, y, data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        self.dragging = false;
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::BezierMode
+    }
+
+    fn key_press_event(
+        &mut self,
+        event: KeyboardEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        self.start?;
+
+        match event.key_code() {
+            //Enter key down.
+            13 => self.finish(data),
+            //Esc key down. Throw away the curve in progress but stay in this mode.
+            27 => {
+                self.start = None;
+                self.segments.clear();
+                self.dragging = false;
+                data.take_preview();
+                data.take_guides();
+                Some(ShouldAction::Rerender(DrawOption::DrawAll))
+            }
+            _ => None,
+        }
+    }
+}
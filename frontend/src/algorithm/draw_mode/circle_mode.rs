@@ -0,0 +1,92 @@
+use lib::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{leaf::circle::Circle, Figure},
+};
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{DrawMode, ShouldAction};
+
+//Draws a circle from its center and a point on the circumference.
+#[derive(Default)]
+pub struct CircleMode {
+    center_x: Option<f64>,
+    center_y: Option<f64>,
+}
+
+impl CircleMode {
+    pub fn new() -> Self {
+        CircleMode {
+            center_x: None,
+            center_y: None,
+        }
+    }
+}
+
+impl DrawMode for CircleMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if let (Some(_), Some(_)) = (self.center_x.take(), self.center_y.take()) {
+            if let Some(preview) = data.take_preview() {
+                let preview = set_radius_to_preview(preview, x, y);
+                return Some(ShouldAction::AddFigure(preview));
+            }
+        } else {
+            self.center_x = Some(x);
+            self.center_y = Some(y);
+            let circle = Circle::new(
+                x,
+                y,
+                0.0,
+                Color::new(0, 0, 0, 255),
+                StrokeStyle::default(),
+                FillStyle::default(),
+            );
+            data.set_preview(Some(Box::new(circle)));
+        }
+        None
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.center_x.is_some() && self.center_y.is_some() {
+            let preview = data.take_preview();
+            if let Some(preview) = preview {
+                let (x, y) = self.convert_figure_coordinates(&event, data);
+                let preview = set_radius_to_preview(preview, x, y);
+                data.set_preview(Some(preview));
+                return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+            }
+        }
+        None
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::CircleMode
+    }
+}
+
+fn set_radius_to_preview(mut preview: Box<dyn Figure>, x: f64, y: f64) -> Box<dyn Figure> {
+    let preview_tmp = preview.as_any_mut();
+    if let Some(circle) = preview_tmp.downcast_mut::<Circle>() {
+        let radius = (x - circle.center_x()).hypot(y - circle.center_y());
+        circle.set_radius(radius);
+    }
+    preview
+}
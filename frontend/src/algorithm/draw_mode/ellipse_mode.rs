@@ -0,0 +1,94 @@
+use lib::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{leaf::ellipse::Ellipse, Figure},
+};
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{DrawMode, ShouldAction};
+
+//Draws an axis aligned ellipse from its center and a corner of its bounding box.
+#[derive(Default)]
+pub struct EllipseMode {
+    center_x: Option<f64>,
+    center_y: Option<f64>,
+}
+
+impl EllipseMode {
+    pub fn new() -> Self {
+        EllipseMode {
+            center_x: None,
+            center_y: None,
+        }
+    }
+}
+
+impl DrawMode for EllipseMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if let (Some(_), Some(_)) = (self.center_x.take(), self.center_y.take()) {
+            if let Some(preview) = data.take_preview() {
+                let preview = set_radii_to_preview(preview, x, y);
+                return Some(ShouldAction::AddFigure(preview));
+            }
+        } else {
+            self.center_x = Some(x);
+            self.center_y = Some(y);
+            let ellipse = Ellipse::new(
+                x,
+                y,
+                0.0,
+                0.0,
+                0.0,
+                Color::new(0, 0, 0, 255),
+                StrokeStyle::default(),
+                FillStyle::default(),
+            );
+            data.set_preview(Some(Box::new(ellipse)));
+        }
+        None
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.center_x.is_some() && self.center_y.is_some() {
+            let preview = data.take_preview();
+            if let Some(preview) = preview {
+                let (x, y) = self.convert_figure_coordinates(&event, data);
+                let preview = set_radii_to_preview(preview, x, y);
+                data.set_preview(Some(preview));
+                return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+            }
+        }
+        None
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::EllipseMode
+    }
+}
+
+fn set_radii_to_preview(mut preview: Box<dyn Figure>, x: f64, y: f64) -> Box<dyn Figure> {
+    let preview_tmp = preview.as_any_mut();
+    if let Some(ellipse) = preview_tmp.downcast_mut::<Ellipse>() {
+        ellipse.set_radius_x((x - ellipse.center_x()).abs());
+        ellipse.set_radius_y((y - ellipse.center_y()).abs());
+    }
+    preview
+}
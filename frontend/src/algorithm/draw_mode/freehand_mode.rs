@@ -0,0 +1,104 @@
+use lib::{
+    common::{Color, StrokeStyle},
+    figure::leaf::stroke::Stroke,
+};
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{DrawMode, ShouldAction};
+
+//Points closer than this to the previous one, in device pixels, are not recorded.
+const MIN_POINT_DISTANCE: f64 = 1.0;
+//Allowed deviation from the drawn path when simplifying, in device pixels.
+const SIMPLIFY_TOLERANCE: f64 = 1.0;
+const DEFAULT_SMOOTHING_ITERATIONS: usize = 1;
+
+//Records the pointer path while the left button is held down.
+//The stroke is smoothed and simplified before it is sent.
+pub struct FreehandMode {
+    stroke: Option<Stroke>,
+    smoothing_iterations: usize,
+}
+
+impl Default for FreehandMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FreehandMode {
+    pub fn new() -> Self {
+        FreehandMode {
+            stroke: None,
+            smoothing_iterations: DEFAULT_SMOOTHING_ITERATIONS,
+        }
+    }
+
+    //0 turns smoothing off.
+    pub fn set_smoothing_iterations(&mut self, iterations: usize) {
+        self.smoothing_iterations = iterations;
+    }
+}
+
+impl DrawMode for FreehandMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if event.button() != 0 {
+            return None;
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        let stroke = Stroke::new(
+            vec![(x, y)],
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        );
+        data.set_preview(Some(Box::new(stroke.clone())));
+        self.stroke = Some(stroke);
+        None
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        let stroke = self.stroke.as_mut()?;
+        if let Some((last_x, last_y)) = stroke.points().last() {
+            let min_distance = MIN_POINT_DISTANCE / data.coordinates().zoom_rate;
+            if (x - last_x).hypot(y - last_y) < min_distance {
+                return None;
+            }
+        }
+
+        stroke.push_point(x, y);
+        data.set_preview(Some(Box::new(stroke.clone())));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let mut stroke = self.stroke.take()?;
+        data.take_preview();
+
+        if stroke.points().len() < 2 {
+            return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+        }
+
+        stroke.smooth(self.smoothing_iterations);
+        stroke.simplify(SIMPLIFY_TOLERANCE / data.coordinates().zoom_rate);
+
+        Some(ShouldAction::AddFigure(Box::new(stroke)))
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::FreehandMode
+    }
+}
@@ -1,5 +1,5 @@
 use lib::{
-    common::Color,
+    common::{Color, StrokeStyle},
     figure::{leaf::line::Line, Figure},
 };
 
@@ -38,7 +38,7 @@ impl DrawMode for LineMode {
         } else {
             self.start_x = Some(x);
             self.start_y = Some(y);
-            let line = Line::new(x, y, x, y, Color::new(0, 0, 0, 255));
+            let line = Line::new(x, y, x, y, Color::new(0, 0, 0, 255), StrokeStyle::default());
             data.set_preview(Some(Box::new(line)));
         }
         None
@@ -1,17 +1,38 @@
-use web_sys::MouseEvent;
+use lib::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{leaf::circle::Circle, Figure},
+};
+use web_sys::{KeyboardEvent, MouseEvent};
 
 use crate::{
     base::{DrawModeType, ShouldAction},
     pages::workspace::draw_area::data::DrawAreaData,
 };
 
-use self::{line_mode::LineMode, select_mode::SelectMode};
+use self::{
+    bezier_mode::BezierMode, circle_mode::CircleMode, ellipse_mode::EllipseMode,
+    freehand_mode::FreehandMode, line_mode::LineMode, polyline_mode::PolylineMode,
+    rectangle_mode::RectangleMode, select_mode::SelectMode, spline_mode::SplineMode,
+    text_mode::TextMode, three_point_arc_mode::ThreePointArcMode,
+    three_point_circle_mode::ThreePointCircleMode,
+};
 
 use super::coordinates_converter::convert_device_to_figure;
 
+pub mod bezier_mode;
+pub mod circle_mode;
+pub mod ellipse_mode;
+pub mod freehand_mode;
 pub mod line_mode;
 pub mod pan_mode;
+pub mod polyline_mode;
+pub mod rectangle_mode;
 pub mod select_mode;
+pub mod spline_mode;
+pub mod text_mode;
+pub mod three_point_arc_mode;
+pub mod three_point_circle_mode;
+pub mod transform_command;
 
 pub trait DrawMode {
     fn mouse_press_event(
@@ -31,6 +52,16 @@ pub trait DrawMode {
     ) -> Option<ShouldAction>;
     fn get_type(&self) -> DrawModeType;
 
+    //Called before the draw area handles the key itself.
+    //Returning Some means the mode consumed the key.
+    fn key_press_event(
+        &mut self,
+        _event: KeyboardEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
     fn convert_figure_coordinates(&self, event: &MouseEvent, data: &DrawAreaData) -> (f64, f64) {
         let x = event.offset_x() as f64;
         let y = event.offset_y() as f64;
@@ -43,7 +74,33 @@ impl From<DrawModeType> for Box<dyn DrawMode> {
         let mode: Box<dyn DrawMode> = match val {
             DrawModeType::SelectMode => Box::new(SelectMode::new()),
             DrawModeType::LineMode => Box::new(LineMode::new()),
+            DrawModeType::RectangleMode => Box::new(RectangleMode::new()),
+            DrawModeType::CircleMode => Box::new(CircleMode::new()),
+            DrawModeType::ThreePointCircleMode => Box::new(ThreePointCircleMode::new()),
+            DrawModeType::ThreePointArcMode => Box::new(ThreePointArcMode::new()),
+            DrawModeType::EllipseMode => Box::new(EllipseMode::new()),
+            DrawModeType::PolylineMode => Box::new(PolylineMode::new()),
+            DrawModeType::BezierMode => Box::new(BezierMode::new()),
+            DrawModeType::SplineMode => Box::new(SplineMode::new()),
+            DrawModeType::TextMode => Box::new(TextMode::new()),
+            DrawModeType::FreehandMode => Box::new(FreehandMode::new()),
         };
         mode
     }
 }
+
+//Radius of a control handle in device pixels.
+const HANDLE_RADIUS: f64 = 4.0;
+
+//A small circle marking a control handle, sized so it looks the same at any zoom.
+pub fn make_handle(x: f64, y: f64, data: &DrawAreaData) -> Box<dyn Figure> {
+    let radius = HANDLE_RADIUS / data.coordinates().zoom_rate;
+    Box::new(Circle::new(
+        x,
+        y,
+        radius,
+        Color::new(0, 149, 255, 255),
+        StrokeStyle::default(),
+        FillStyle::default(),
+    ))
+}
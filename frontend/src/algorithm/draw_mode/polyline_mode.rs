@@ -0,0 +1,138 @@
+use lib::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{leaf::polyline::Polyline, Figure},
+};
+use web_sys::KeyboardEvent;
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{DrawMode, ShouldAction};
+
+//How close, in device pixels, a click has to be to the first vertex to close the polyline.
+const CLOSE_TOLERANCE: f64 = 8.0;
+
+//Adds a vertex on each click.
+//Finishes on double click or Enter, or closes the polyline on a click at the first vertex.
+#[derive(Default)]
+pub struct PolylineMode {
+    vertices: Vec<(f64, f64)>,
+}
+
+impl PolylineMode {
+    pub fn new() -> Self {
+        PolylineMode {
+            vertices: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, closed: bool, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let vertices = std::mem::take(&mut self.vertices);
+        data.take_preview();
+
+        let minimum = if closed { 3 } else { 2 };
+        if vertices.len() < minimum {
+            return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+        }
+
+        let polyline = Polyline::new(
+            vertices,
+            closed,
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+            FillStyle::default(),
+        );
+        Some(ShouldAction::AddFigure(Box::new(polyline)))
+    }
+
+    fn is_first_vertex(&self, x: f64, y: f64, data: &DrawAreaData) -> bool {
+        if let Some((first_x, first_y)) = self.vertices.first() {
+            let tolerance = CLOSE_TOLERANCE / data.coordinates().zoom_rate;
+            (x - first_x).hypot(y - first_y) <= tolerance
+        } else {
+            false
+        }
+    }
+}
+
+impl DrawMode for PolylineMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        //The first click of a double click already added the last vertex.
+        if event.detail() >= 2 {
+            return self.finish(false, data);
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if self.vertices.len() >= 3 && self.is_first_vertex(x, y, data) {
+            return self.finish(true, data);
+        }
+
+        self.vertices.push((x, y));
+        data.set_preview(Some(make_preview(&self.vertices, x, y)));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        data.set_preview(Some(make_preview(&self.vertices, x, y)));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::PolylineMode
+    }
+
+    fn key_press_event(
+        &mut self,
+        event: KeyboardEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+
+        match event.key_code() {
+            //Enter key down.
+            13 => self.finish(false, data),
+            //Esc key down. Throw away the figure in progress but stay in this mode.
+            27 => {
+                self.vertices.clear();
+                data.take_preview();
+                Some(ShouldAction::Rerender(DrawOption::DrawAll))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn make_preview(vertices: &[(f64, f64)], x: f64, y: f64) -> Box<dyn Figure> {
+    let mut polyline = Polyline::new(
+        vertices.to_vec(),
+        false,
+        Color::new(0, 0, 0, 255),
+        StrokeStyle::default(),
+        FillStyle::default(),
+    );
+    polyline.push_vertex(x, y);
+    Box::new(polyline)
+}
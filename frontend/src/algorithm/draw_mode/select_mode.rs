@@ -1,42 +1,764 @@
-use crate::pages::workspace::draw_area::data::DrawAreaData;
+use std::collections::HashMap;
 
-use super::{DrawMode, ShouldAction};
+use lib::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{
+        block::{BlockDefinition, BlockName, MAX_BLOCK_DEPTH},
+        composite::group::Group,
+        leaf::{block_ref::BlockRef, rectangle::Rectangle},
+        Figure, FigureId,
+    },
+    geometry::{distance::shape_touches_area, BoundingBox, Point, Transform},
+};
+use web_sys::{KeyboardEvent, MouseEvent};
+
+use crate::{
+    algorithm::{
+        coordinates_converter::{convert_device_to_figure, convert_figure_to_device},
+        grip::{grips, move_grip},
+    },
+    base::DrawOption,
+    pages::workspace::draw_area::data::DrawAreaData,
+};
+
+use super::{
+    transform_command::{TransformCommand, TransformKind},
+    DrawMode, ShouldAction,
+};
+
+//How far from a figure a click still picks it, in device pixels.
+const PICK_TOLERANCE: f64 = 5.0;
+
+//The mouse has to move this far, in device pixels, before a press becomes a drag.
+const DRAG_THRESHOLD: f64 = 3.0;
+
+//Dash pattern of the selection rectangle when it selects crossing figures.
+const CROSSING_DASH: [f64; 2] = [6.0, 4.0];
+
+//How far outside a crossing selection rectangle a figure may pass and still be
+//selected, in device pixels.
+const CROSSING_TOLERANCE: f64 = 1.0;
+
+//How a click or a selection rectangle combines with the current selection.
+#[derive(Clone, Copy, PartialEq)]
+enum SelectionChange {
+    Replace,
+    Add,
+    Toggle,
+}
+
+impl SelectionChange {
+    fn from_event(event: &MouseEvent) -> Self {
+        if event.ctrl_key() || event.meta_key() {
+            SelectionChange::Toggle
+        } else if event.shift_key() {
+            SelectionChange::Add
+        } else {
+            SelectionChange::Replace
+        }
+    }
+}
+
+//What a press turns into once the mouse moves past DRAG_THRESHOLD.
+#[derive(Clone, Copy, PartialEq, Default)]
+enum Drag {
+    //Not moved far enough yet.
+    #[default]
+    None,
+    SelectionBox,
+    //Moves the selection, or copies it when Ctrl is held on release.
+    Figures,
+    Grip,
+}
 
 #[derive(Default)]
-pub struct SelectMode {}
+pub struct SelectMode {
+    //Device position where the left button went down, None while it is up.
+    press_pos: Option<(f64, f64)>,
+    //Figure under the press, which a drag moves along with the rest of the selection.
+    grabbed: Option<FigureId>,
+    //Grip under the press, as the figure and the grip's index in grips().
+    grip: Option<(FigureId, usize)>,
+    drag: Drag,
+    command: Option<TransformCommand>,
+}
 
 impl SelectMode {
     pub fn new() -> Self {
-        SelectMode {}
+        SelectMode {
+            press_pos: None,
+            grabbed: None,
+            grip: None,
+            drag: Drag::None,
+            command: None,
+        }
+    }
+
+    fn start_command(
+        &mut self,
+        kind: TransformKind,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if data.selected().is_empty() {
+            return None;
+        }
+        self.command = Some(TransformCommand::new(kind));
+        //Drop the preview of a command that was running before.
+        data.take_preview()
+            .map(|_| ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn cancel_command(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        self.command.take()?;
+        data.take_preview();
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    //Each click adds a point to the command until it has all it needs.
+    fn command_press(
+        &mut self,
+        event: &MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let point: Point = self.convert_figure_coordinates(event, data).into();
+        let command = self.command.as_mut()?;
+        if !command.is_ready() {
+            command.push_point(point);
+            return self.command_preview(point, data);
+        }
+
+        //A degenerate transform, like a mirror axis of one point, waits for another click.
+        let transform = command.transform(point)?;
+        self.finish_command(&transform, data)
+    }
+
+    fn command_preview(&self, cursor: Point, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let transform = self.command.as_ref()?.transform(cursor)?;
+        data.set_preview(Some(Box::new(Group::new(transformed_selection(
+            data, &transform,
+        )))));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn finish_command(
+        &mut self,
+        transform: &Transform,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let command = self.command.take()?;
+        data.take_preview();
+
+        let copy = match command.kind() {
+            TransformKind::Copy => true,
+            TransformKind::Mirror => !web_sys::window()?
+                .confirm_with_message("Erase the original figures?")
+                .unwrap_or(false),
+            TransformKind::Move | TransformKind::Rotate | TransformKind::Scale => false,
+        };
+        Some(transform_selection(data, transform, copy))
+    }
+
+    //Finds a grip of a selected figure under the press. Figures drawn on top come first.
+    fn pick_grip(
+        &self,
+        device_x: f64,
+        device_y: f64,
+        data: &DrawAreaData,
+    ) -> Option<(FigureId, usize)> {
+        let list = data.figures().list();
+        let list = list.borrow();
+        list.iter().rev().find_map(|figure| {
+            let id = selected_id(figure.as_ref(), data.selected())?;
+            let index = grips(figure.as_ref()).into_iter().position(|(x, y)| {
+                let (x, y) = convert_figure_to_device(data.coordinates(), x, y);
+                (x - device_x).hypot(y - device_y) <= PICK_TOLERANCE
+            })?;
+            Some((id, index))
+        })
+    }
+
+    //The grabbed figure reshaped so its grip is at the mouse.
+    fn reshaped(&self, event: &MouseEvent, data: &DrawAreaData) -> Option<Box<dyn Figure>> {
+        let (figure_id, index) = self.grip?;
+        let list = data.figures().list();
+        let list = list.borrow();
+        let figure = list.iter().find(|figure| figure.id() == Some(figure_id))?;
+
+        let mut figure: Box<dyn Figure> = figure.data().into();
+        let (x, y) = self.convert_figure_coordinates(event, data);
+        move_grip(figure.as_mut(), index, x, y);
+        Some(figure)
+    }
+
+    fn start_drag(&mut self, event: &MouseEvent, data: &mut DrawAreaData) {
+        if self.grip.is_some() {
+            self.drag = Drag::Grip;
+            return;
+        }
+
+        match self.grabbed {
+            Some(id) => {
+                //Grabbing an unselected figure drags just that figure, or adds it with Shift.
+                if !data.selected().contains(&id) {
+                    let change = if event.shift_key() {
+                        SelectionChange::Add
+                    } else {
+                        SelectionChange::Replace
+                    };
+                    self.apply_selection(vec![id], change, data);
+                }
+                self.drag = Drag::Figures;
+            }
+            None => self.drag = Drag::SelectionBox,
+        }
+    }
+
+    //Picks the figure closest to the click. On a tie the one drawn on top wins.
+    fn pick(&self, device_x: f64, device_y: f64, data: &DrawAreaData) -> Option<FigureId> {
+        let (x, y) = convert_device_to_figure(data.coordinates(), device_x, device_y);
+        let point = Point::new(x, y);
+        let tolerance = PICK_TOLERANCE / data.coordinates().zoom_rate;
+
+        let blocks = data.blocks().list();
+        let blocks = blocks.borrow();
+        let list = data.figures().list();
+        let list = list.borrow();
+
+        let mut nearest = None;
+        let mut nearest_distance = tolerance;
+        for figure in list.iter() {
+            let distance = figure_distance(figure.as_ref(), point, &blocks);
+            if distance <= nearest_distance {
+                if let Some(id) = figure.id() {
+                    nearest = Some(id);
+                    nearest_distance = distance;
+                }
+            }
+        }
+        nearest
+    }
+
+    //Dragging left to right selects figures fully inside the rectangle (window),
+    //right to left selects every figure it touches (crossing).
+    fn select_in_box(
+        &self,
+        start: (f64, f64),
+        end: (f64, f64),
+        data: &DrawAreaData,
+    ) -> Vec<FigureId> {
+        let crossing = end.0 < start.0;
+        let selection_box = figure_box(start, end, data);
+        let tolerance = CROSSING_TOLERANCE / data.coordinates().zoom_rate;
+
+        let blocks = data.blocks().list();
+        let blocks = blocks.borrow();
+        let list = data.figures().list();
+        let list = list.borrow();
+
+        list.iter()
+            .filter(|figure| {
+                if crossing {
+                    crosses(figure.as_ref(), &selection_box, &blocks, tolerance)
+                } else {
+                    figure_bounding_box(figure.as_ref(), &blocks)
+                        .is_some_and(|bounding_box| selection_box.contains_box(&bounding_box))
+                }
+            })
+            .filter_map(|figure| figure.id())
+            .collect()
+    }
+
+    fn apply_selection(
+        &self,
+        found: Vec<FigureId>,
+        change: SelectionChange,
+        data: &mut DrawAreaData,
+    ) {
+        let mut selected = match change {
+            SelectionChange::Replace => Vec::new(),
+            SelectionChange::Add | SelectionChange::Toggle => data.selected().to_vec(),
+        };
+        for id in found {
+            match selected.iter().position(|selected_id| *selected_id == id) {
+                Some(index) if change == SelectionChange::Toggle => {
+                    selected.remove(index);
+                }
+                Some(_) => {}
+                None => selected.push(id),
+            }
+        }
+        data.set_selected(selected);
+    }
+
+    fn select_all(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let list = data.figures().list();
+        let selected = list
+            .borrow()
+            .iter()
+            .filter_map(|figure| figure.id())
+            .collect();
+        data.set_selected(selected);
+        None
+    }
+
+    //Puts the selected figures into one group, keeping their drawing order.
+    fn group(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        if data.selected().len() < 2 {
+            return None;
+        }
+
+        let mut figure_ids = Vec::new();
+        let mut children: Vec<Box<dyn Figure>> = Vec::new();
+        for figure in data.figures().list().borrow().iter() {
+            if let Some(id) = selected_id(figure.as_ref(), data.selected()) {
+                figure_ids.push(id);
+                children.push(figure.data().into());
+            }
+        }
+
+        data.set_selected(Vec::new());
+        Some(ShouldAction::ReplaceFigures(
+            figure_ids,
+            vec![Box::new(Group::new(children))],
+        ))
+    }
+
+    //Replaces each selected group with its children.
+    fn ungroup(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let mut figure_ids = Vec::new();
+        let mut children = Vec::new();
+        for figure in data.figures().list().borrow().iter() {
+            let id = selected_id(figure.as_ref(), data.selected());
+            let group = figure.as_any().downcast_ref::<Group>();
+            if let (Some(id), Some(group)) = (id, group) {
+                figure_ids.push(id);
+                children.extend(group.clone().into_children());
+            }
+        }
+
+        if figure_ids.is_empty() {
+            return None;
+        }
+
+        data.set_selected(Vec::new());
+        Some(ShouldAction::ReplaceFigures(figure_ids, children))
+    }
+
+    //Asks for a name and turns the selected figures into a block.
+    //Using an existing name redefines that block for every instance.
+    fn make_block(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        if data.selected().is_empty() {
+            return None;
+        }
+
+        let name = web_sys::window()?
+            .prompt_with_message("Block name")
+            .ok()
+            .flatten()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut figure_ids = Vec::new();
+        let mut figures = Vec::new();
+        for figure in data.figures().list().borrow().iter() {
+            if let Some(id) = selected_id(figure.as_ref(), data.selected()) {
+                figure_ids.push(id);
+                figures.push(figure.data());
+            }
+        }
+
+        data.set_selected(Vec::new());
+        Some(ShouldAction::MakeBlock(
+            BlockDefinition::new(name.to_string(), figures),
+            figure_ids,
+        ))
     }
 }
 
 impl DrawMode for SelectMode {
     fn mouse_press_event(
         &mut self,
-        _event: web_sys::MouseEvent,
-        _data: &mut DrawAreaData,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
     ) -> Option<ShouldAction> {
+        if event.button() != 0 {
+            return None;
+        }
+        if self.command.is_some() {
+            return self.command_press(&event, data);
+        }
+
+        let press_pos = (event.offset_x() as f64, event.offset_y() as f64);
+        self.press_pos = Some(press_pos);
+        self.grip = self.pick_grip(press_pos.0, press_pos.1, data);
+        self.grabbed = self.pick(press_pos.0, press_pos.1, data);
+        self.drag = Drag::None;
         None
     }
 
     fn mouse_mouse_event(
         &mut self,
-        _event: web_sys::MouseEvent,
-        _data: &mut DrawAreaData,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
     ) -> Option<ShouldAction> {
-        None
+        if self.command.is_some() {
+            let cursor = self.convert_figure_coordinates(&event, data).into();
+            return self.command_preview(cursor, data);
+        }
+
+        let start = self.press_pos?;
+        let end = (event.offset_x() as f64, event.offset_y() as f64);
+        if self.drag == Drag::None {
+            if (end.0 - start.0).hypot(end.1 - start.1) < DRAG_THRESHOLD {
+                return None;
+            }
+            self.start_drag(&event, data);
+        }
+
+        match self.drag {
+            Drag::SelectionBox => {
+                data.set_guides(vec![selection_rectangle(start, end, data)]);
+            }
+            Drag::Figures => {
+                let (dx, dy) = device_offset_to_figure(start, end, data);
+                let transform = Transform::translation(dx, dy);
+                data.set_preview(Some(Box::new(Group::new(transformed_selection(
+                    data, &transform,
+                )))));
+            }
+            Drag::Grip => {
+                let preview = self.reshaped(&event, data);
+                data.set_preview(preview);
+            }
+            Drag::None => {}
+        }
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
     }
 
     fn mouse_release_event(
         &mut self,
-        _event: web_sys::MouseEvent,
-        _data: &mut DrawAreaData,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
     ) -> Option<ShouldAction> {
-        None
+        let start = self.press_pos.take()?;
+        let end = (event.offset_x() as f64, event.offset_y() as f64);
+        self.grabbed = None;
+
+        let found = match std::mem::take(&mut self.drag) {
+            Drag::Figures => {
+                data.take_preview();
+                let (dx, dy) = device_offset_to_figure(start, end, data);
+                let copy = event.ctrl_key() || event.meta_key();
+                return Some(transform_selection(
+                    data,
+                    &Transform::translation(dx, dy),
+                    copy,
+                ));
+            }
+            Drag::Grip => {
+                data.take_preview();
+                let figure = self.reshaped(&event, data);
+                self.grip = None;
+                return figure.map(|figure| ShouldAction::UpdateFigures(vec![figure]));
+            }
+            Drag::SelectionBox => {
+                data.take_guides();
+                self.select_in_box(start, end, data)
+            }
+            Drag::None => self.pick(end.0, end.1, data).into_iter().collect(),
+        };
+
+        self.grip = None;
+        self.apply_selection(found, SelectionChange::from_event(&event), data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
     }
 
     fn get_type(&self) -> super::DrawModeType {
         super::DrawModeType::SelectMode
     }
+
+    fn key_press_event(
+        &mut self,
+        event: KeyboardEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if !event.ctrl_key() && !event.meta_key() {
+            return match event.key_code() {
+                //Shift + M key down.
+                77 if event.shift_key() => self.start_command(TransformKind::Mirror, data),
+                //M key down.
+                77 => self.start_command(TransformKind::Move, data),
+                //C key down.
+                67 => self.start_command(TransformKind::Copy, data),
+                //R key down.
+                82 => self.start_command(TransformKind::Rotate, data),
+                //S key down.
+                83 => self.start_command(TransformKind::Scale, data),
+                //Enter key down. Asks for a typed angle or factor instead of points.
+                13 => {
+                    let transform = self.command.as_ref()?.typed_transform()?;
+                    self.finish_command(&transform, data)
+                }
+                //Esc key down.
+                27 => self.cancel_command(data),
+                //Delete or Backspace key down.
+                46 | 8 if self.command.is_none() && !data.selected().is_empty() => {
+                    event.prevent_default();
+                    let figure_ids = data.selected().to_vec();
+                    data.set_selected(Vec::new());
+                    Some(ShouldAction::DeleteFigures(figure_ids))
+                }
+                _ => None,
+            };
+        }
+
+        match event.key_code() {
+            //Ctrl + A key down.
+            65 => {
+                event.prevent_default();
+                self.select_all(data)
+            }
+            //Ctrl + B key down.
+            66 => {
+                event.prevent_default();
+                self.make_block(data)
+            }
+            //Ctrl + Shift + G key down.
+            71 if event.shift_key() => {
+                event.prevent_default();
+                self.ungroup(data)
+            }
+            //Ctrl + G key down.
+            71 => {
+                event.prevent_default();
+                self.group(data)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn selected_id(figure: &dyn Figure, selected: &[FigureId]) -> Option<FigureId> {
+    figure.id().filter(|id| selected.contains(id))
+}
+
+//The selection rectangle between two device positions, in figure coordinates.
+fn figure_box(start: (f64, f64), end: (f64, f64), data: &DrawAreaData) -> BoundingBox {
+    let start = convert_device_to_figure(data.coordinates(), start.0, start.1);
+    let end = convert_device_to_figure(data.coordinates(), end.0, end.1);
+    BoundingBox::new(start.into(), end.into())
+}
+
+//Drawn as a guide while dragging. A crossing selection is dashed.
+fn selection_rectangle(start: (f64, f64), end: (f64, f64), data: &DrawAreaData) -> Box<dyn Figure> {
+    let selection_box = figure_box(start, end, data);
+    let mut stroke_style = StrokeStyle::default();
+    if end.0 < start.0 {
+        stroke_style.dash_pattern = CROSSING_DASH.to_vec();
+    }
+    Box::new(Rectangle::new(
+        selection_box.min.x,
+        selection_box.min.y,
+        selection_box.width(),
+        selection_box.height(),
+        0.0,
+        Color::new(0, 149, 255, 255),
+        stroke_style,
+        FillStyle::None,
+    ))
+}
+
+//Whether the figure itself passes through the selection box, not just its bounding box.
+fn crosses(
+    figure: &dyn Figure,
+    selection_box: &BoundingBox,
+    blocks: &HashMap<BlockName, BlockDefinition>,
+    tolerance: f64,
+) -> bool {
+    let Some(bounding_box) = figure_bounding_box(figure, blocks) else {
+        return false;
+    };
+    if !selection_box.expand(tolerance).intersects(&bounding_box) {
+        return false;
+    }
+    selection_box.contains_box(&bounding_box)
+        || shape_touches_area(
+            &|point| figure_distance(figure, point, blocks),
+            selection_box,
+            tolerance,
+        )
+}
+
+fn figure_bounding_box(
+    figure: &dyn Figure,
+    blocks: &HashMap<BlockName, BlockDefinition>,
+) -> Option<BoundingBox> {
+    nested_bounding_box(figure, blocks, 0)
+}
+
+fn figure_distance(
+    figure: &dyn Figure,
+    point: Point,
+    blocks: &HashMap<BlockName, BlockDefinition>,
+) -> f64 {
+    nested_distance(figure, point, blocks, 0)
+}
+
+//Block instances only know their insertion point, so they are measured through their
+//definition. Groups are measured through their children, which may be instances too.
+fn nested_bounding_box(
+    figure: &dyn Figure,
+    blocks: &HashMap<BlockName, BlockDefinition>,
+    depth: usize,
+) -> Option<BoundingBox> {
+    if let Some(group) = figure.as_any().downcast_ref::<Group>() {
+        return group
+            .children()
+            .iter()
+            .filter_map(|child| nested_bounding_box(child.as_ref(), blocks, depth))
+            .reduce(|a, b| a.union(&b));
+    }
+    let block_ref = figure.as_any().downcast_ref::<BlockRef>();
+    if let Some(block_ref) = block_ref.filter(|_| depth < MAX_BLOCK_DEPTH) {
+        if let Some(definition) = blocks.get(block_ref.name()) {
+            let transform = block_ref.instance_transform();
+            return definition
+                .figures
+                .iter()
+                .filter_map(|data| {
+                    let figure: Box<dyn Figure> = data.clone().into();
+                    nested_bounding_box(figure.as_ref(), blocks, depth + 1)
+                })
+                .map(|bounding_box| bounding_box.transformed(&transform))
+                .reduce(|a, b| a.union(&b));
+        }
+    }
+    figure.bounding_box()
+}
+
+//Measured in the definition's coordinates for block instances, then scaled back.
+//Only exact for uniform scales.
+fn nested_distance(
+    figure: &dyn Figure,
+    point: Point,
+    blocks: &HashMap<BlockName, BlockDefinition>,
+    depth: usize,
+) -> f64 {
+    if let Some(group) = figure.as_any().downcast_ref::<Group>() {
+        return group
+            .children()
+            .iter()
+            .map(|child| nested_distance(child.as_ref(), point, blocks, depth))
+            .fold(f64::INFINITY, f64::min);
+    }
+    let block_ref = figure.as_any().downcast_ref::<BlockRef>();
+    if let Some(block_ref) = block_ref.filter(|_| depth < MAX_BLOCK_DEPTH) {
+        let transform = block_ref.instance_transform();
+        if let (Some(definition), Some(inverse)) =
+            (blocks.get(block_ref.name()), transform.inverse())
+        {
+            let local = inverse.apply(point);
+            return definition
+                .figures
+                .iter()
+                .map(|data| {
+                    let figure: Box<dyn Figure> = data.clone().into();
+                    nested_distance(figure.as_ref(), local, blocks, depth + 1)
+                })
+                .fold(f64::INFINITY, f64::min)
+                * transform.scale_factor();
+        }
+    }
+    figure.distance_to(point)
+}
+
+//Offset between two device positions in figure units.
+fn device_offset_to_figure(start: (f64, f64), end: (f64, f64), data: &DrawAreaData) -> (f64, f64) {
+    let start = convert_device_to_figure(data.coordinates(), start.0, start.1);
+    let end = convert_device_to_figure(data.coordinates(), end.0, end.1);
+    (end.0 - start.0, end.1 - start.1)
+}
+
+//Copies of the selected figures with the transform applied. They keep their ids.
+fn transformed_selection(data: &DrawAreaData, transform: &Transform) -> Vec<Box<dyn Figure>> {
+    let list = data.figures().list();
+    let list = list.borrow();
+    list.iter()
+        .filter(|figure| selected_id(figure.as_ref(), data.selected()).is_some())
+        .map(|figure| {
+            let mut figure: Box<dyn Figure> = figure.data().into();
+            figure.transform(transform);
+            figure
+        })
+        .collect()
+}
+
+//Sends the transformed figures as updates, or as additions when copying.
+//The server gives copies new ids.
+fn transform_selection(data: &DrawAreaData, transform: &Transform, copy: bool) -> ShouldAction {
+    let figures = transformed_selection(data, transform);
+    if copy {
+        ShouldAction::AddFigures(figures)
+    } else {
+        ShouldAction::UpdateFigures(figures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::figure::leaf::line::Line;
+
+    use super::*;
+
+    #[test]
+    fn crossing_needs_the_figure_not_its_bounding_box() {
+        let line = Line::new(
+            0.0,
+            0.0,
+            10.0,
+            10.0,
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        );
+        let blocks = HashMap::new();
+        let area = |x1, y1, x2, y2| BoundingBox::new(Point::new(x1, y1), Point::new(x2, y2));
+
+        //Both inside the line's bounding box, on either side of the diagonal.
+        assert!(!crosses(&line, &area(6.0, 0.0, 10.0, 4.0), &blocks, 0.1));
+        assert!(!crosses(&line, &area(0.0, 6.0, 4.0, 10.0), &blocks, 0.1));
+        assert!(crosses(&line, &area(4.0, 0.0, 6.0, 10.0), &blocks, 0.1));
+        assert!(crosses(&line, &area(-5.0, -5.0, 20.0, 20.0), &blocks, 0.1));
+        assert!(!crosses(&line, &area(11.0, 11.0, 20.0, 20.0), &blocks, 0.1));
+    }
+
+    #[test]
+    fn block_ref_in_group_is_measured_through_its_definition() {
+        let line = Line::new(
+            0.0,
+            0.0,
+            10.0,
+            0.0,
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        );
+        let name = BlockName::from("part");
+        let blocks = HashMap::from([(
+            name.clone(),
+            BlockDefinition::new(name.clone(), vec![line.data()]),
+        )]);
+        let block_ref = BlockRef::new(name, 100.0, 50.0, 2.0, 2.0, 0.0);
+        let group = Group::new(vec![Box::new(block_ref)]);
+
+        let bounding_box = figure_bounding_box(&group, &blocks).unwrap();
+        assert!((bounding_box.min.x - 100.0).abs() < 1e-9);
+        assert!((bounding_box.max.x - 120.0).abs() < 1e-9);
+        assert!((bounding_box.min.y - 50.0).abs() < 1e-9);
+        assert!((bounding_box.max.y - 50.0).abs() < 1e-9);
+
+        assert!(figure_distance(&group, Point::new(115.0, 50.0), &blocks) < 1e-9);
+        let distance = figure_distance(&group, Point::new(115.0, 53.0), &blocks);
+        assert!((distance - 3.0).abs() < 1e-9);
+    }
 }
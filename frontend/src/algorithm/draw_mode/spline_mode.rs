@@ -0,0 +1,121 @@
+use lib::{
+    common::{Color, StrokeStyle},
+    figure::{leaf::spline::Spline, Figure},
+};
+use web_sys::KeyboardEvent;
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{make_handle, DrawMode, ShouldAction};
+
+//Adds a fit point on each click and shows a handle on every fit point.
+//Finishes on double click or Enter, Esc throws the spline away.
+#[derive(Default)]
+pub struct SplineMode {
+    fit_points: Vec<(f64, f64)>,
+}
+
+impl SplineMode {
+    pub fn new() -> Self {
+        SplineMode {
+            fit_points: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let fit_points = std::mem::take(&mut self.fit_points);
+        data.take_preview();
+        data.take_guides();
+
+        if fit_points.len() < 2 {
+            return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+        }
+
+        let spline = Spline::new(fit_points, Color::new(0, 0, 0, 255), StrokeStyle::default());
+        Some(ShouldAction::AddFigure(Box::new(spline)))
+    }
+
+    fn update_preview(&self, x: f64, y: f64, data: &mut DrawAreaData) {
+        let mut spline = Spline::new(
+            self.fit_points.clone(),
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        );
+        spline.push_fit_point(x, y);
+        data.set_preview(Some(Box::new(spline)));
+
+        let guides: Vec<Box<dyn Figure>> = self
+            .fit_points
+            .iter()
+            .map(|(x, y)| make_handle(*x, *y, data))
+            .collect();
+        data.set_guides(guides);
+    }
+}
+
+impl DrawMode for SplineMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        //The first click of a double click already added the last fit point.
+        if event.detail() >= 2 {
+            return self.finish(data);
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        self.fit_points.push((x, y));
+        self.update_preview(x, y, data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.fit_points.is_empty() {
+            return None;
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        self.update_preview(x, y, data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::SplineMode
+    }
+
+    fn key_press_event(
+        &mut self,
+        event: KeyboardEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.fit_points.is_empty() {
+            return None;
+        }
+
+        match event.key_code() {
+            //Enter key down.
+            13 => self.finish(data),
+            //Esc key down. Throw away the spline in progress but stay in this mode.
+            27 => {
+                self.fit_points.clear();
+                data.take_preview();
+                data.take_guides();
+                Some(ShouldAction::Rerender(DrawOption::DrawAll))
+            }
+            _ => None,
+        }
+    }
+}
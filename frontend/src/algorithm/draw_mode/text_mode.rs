@@ -0,0 +1,178 @@
+use lib::{
+    common::{Color, StrokeStyle},
+    figure::{
+        leaf::{
+            line::Line,
+            text::{Text, TextAlignment},
+        },
+        Figure,
+    },
+};
+use web_sys::KeyboardEvent;
+
+use crate::{
+    algorithm::visitor::drawer::text_font, base::DrawOption,
+    pages::workspace::draw_area::data::DrawAreaData,
+};
+
+use super::{DrawMode, ShouldAction};
+
+//Font size of new text in figure units.
+const DEFAULT_FONT_SIZE: f64 = 20.0;
+
+//Clicking opens an editor at the clicked spot. Typed keys go into the text,
+//Enter or a click elsewhere commits it and Esc throws it away.
+#[derive(Default)]
+pub struct TextMode {
+    editing: Option<Text>,
+}
+
+impl TextMode {
+    pub fn new() -> Self {
+        TextMode { editing: None }
+    }
+
+    fn commit(&mut self, data: &mut DrawAreaData) -> Option<ShouldAction> {
+        let text = self.editing.take()?;
+        data.take_preview();
+        data.take_guides();
+
+        if text.text().is_empty() {
+            Some(ShouldAction::Rerender(DrawOption::DrawAll))
+        } else {
+            Some(ShouldAction::AddFigure(Box::new(text)))
+        }
+    }
+
+    fn update_preview(&self, data: &mut DrawAreaData) {
+        if let Some(text) = self.editing.as_ref() {
+            let caret = make_caret(text, data);
+            data.set_preview(Some(Box::new(text.clone())));
+            data.set_guides(vec![caret]);
+        }
+    }
+}
+
+impl DrawMode for TextMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let committed = self.commit(data);
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        self.editing = Some(Text::new(
+            x,
+            y,
+            String::new(),
+            DEFAULT_FONT_SIZE,
+            TextAlignment::Left,
+            0.0,
+            Color::new(0, 0, 0, 255),
+            None,
+        ));
+        self.update_preview(data);
+
+        match committed {
+            Some(ShouldAction::AddFigure(figure)) => Some(ShouldAction::AddFigure(figure)),
+            _ => Some(ShouldAction::Rerender(DrawOption::DrawAll)),
+        }
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::TextMode
+    }
+
+    fn key_press_event(
+        &mut self,
+        event: KeyboardEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let text = self.editing.as_mut()?;
+
+        if event.ctrl_key() || event.meta_key() || event.alt_key() {
+            return None;
+        }
+
+        let key = event.key();
+        match event.key_code() {
+            //Enter key down.
+            13 => {
+                event.prevent_default();
+                return self.commit(data);
+            }
+            //Esc key down. Throw away the text but stay in this mode.
+            27 => {
+                self.editing = None;
+                data.take_preview();
+                data.take_guides();
+                return Some(ShouldAction::Rerender(DrawOption::DrawAll));
+            }
+            //Backspace key down.
+            8 => {
+                text.pop_char();
+            }
+            _ => {
+                //Printable keys have a single character name, others like "Shift" don't.
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => text.push_char(c),
+                    _ => return None,
+                }
+            }
+        }
+
+        event.prevent_default();
+        self.update_preview(data);
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+}
+
+fn make_caret(text: &Text, data: &DrawAreaData) -> Box<dyn Figure> {
+    let coordinates = data.coordinates();
+
+    let context = data.convert_2d_context();
+    context.set_font(&text_font(text, coordinates));
+    let device_width = context
+        .measure_text(text.text())
+        .map(|metrics| metrics.width())
+        .unwrap_or(0.0);
+    let width = device_width / coordinates.zoom_rate;
+
+    let offset = match text.alignment() {
+        TextAlignment::Left => width,
+        TextAlignment::Center => width / 2.0,
+        TextAlignment::Right => 0.0,
+    };
+
+    let (sin, cos) = text.rotation().sin_cos();
+    let bottom = (text.x() + offset * cos, text.y() + offset * sin);
+    let height = text.font_size();
+    let top = (bottom.0 - height * sin, bottom.1 + height * cos);
+
+    Box::new(Line::new(
+        bottom.0,
+        bottom.1,
+        top.0,
+        top.1,
+        Color::new(0, 0, 0, 255),
+        StrokeStyle::default(),
+    ))
+}
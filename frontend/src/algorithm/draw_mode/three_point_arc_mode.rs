@@ -0,0 +1,99 @@
+use lib::{
+    common::{Color, StrokeStyle},
+    figure::{
+        leaf::{arc::Arc, line::Line},
+        Figure,
+    },
+};
+
+use crate::{base::DrawOption, pages::workspace::draw_area::data::DrawAreaData};
+
+use super::{DrawMode, ShouldAction};
+
+//Draws an arc from the first clicked point, through the second, to the third.
+#[derive(Default)]
+pub struct ThreePointArcMode {
+    points: Vec<(f64, f64)>,
+}
+
+impl ThreePointArcMode {
+    pub fn new() -> Self {
+        ThreePointArcMode { points: Vec::new() }
+    }
+}
+
+impl DrawMode for ThreePointArcMode {
+    fn mouse_press_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+
+        if self.points.len() == 2 {
+            //Collinear points don't make an arc, so keep waiting for a valid third point.
+            let arc = Arc::from_three_points(
+                self.points[0],
+                self.points[1],
+                (x, y),
+                Color::new(0, 0, 0, 255),
+                StrokeStyle::default(),
+            )?;
+            self.points.clear();
+            data.take_preview();
+            return Some(ShouldAction::AddFigure(Box::new(arc)));
+        }
+
+        self.points.push((x, y));
+        data.set_preview(Some(make_preview(&self.points, x, y)));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_mouse_event(
+        &mut self,
+        event: web_sys::MouseEvent,
+        data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        if self.points.is_empty() {
+            return None;
+        }
+
+        let (x, y) = self.convert_figure_coordinates(&event, data);
+        data.set_preview(Some(make_preview(&self.points, x, y)));
+        Some(ShouldAction::Rerender(DrawOption::DrawAll))
+    }
+
+    fn mouse_release_event(
+        &mut self,
+        _event: web_sys::MouseEvent,
+        _data: &mut DrawAreaData,
+    ) -> Option<ShouldAction> {
+        None
+    }
+
+    fn get_type(&self) -> super::DrawModeType {
+        super::DrawModeType::ThreePointArcMode
+    }
+}
+
+fn make_preview(points: &[(f64, f64)], x: f64, y: f64) -> Box<dyn Figure> {
+    let color = Color::new(0, 0, 0, 255);
+    let (start_x, start_y) = points[0];
+
+    if points.len() == 2 {
+        if let Some(arc) =
+            Arc::from_three_points(points[0], points[1], (x, y), color, StrokeStyle::default())
+        {
+            return Box::new(arc);
+        }
+    }
+
+    Box::new(Line::new(
+        start_x,
+        start_y,
+        x,
+        y,
+        color,
+        StrokeStyle::default(),
+    ))
+}
@@ -0,0 +1,122 @@
+use lib::geometry::{Point, Transform, TOLERANCE};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransformKind {
+    Move,
+    Copy,
+    Rotate,
+    Scale,
+    Mirror,
+}
+
+//Collects the points a transform command needs, then turns the last one (the cursor
+//while previewing) into a Transform.
+//Move and copy: base point, target point.
+//Rotate: base point, reference point, target point. The angle between them is the rotation.
+//Scale: base point, reference point, target point. The ratio of their distances is the factor.
+//Mirror: two points on the axis.
+pub struct TransformCommand {
+    kind: TransformKind,
+    points: Vec<Point>,
+}
+
+impl TransformCommand {
+    pub fn new(kind: TransformKind) -> Self {
+        Self {
+            kind,
+            points: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> TransformKind {
+        self.kind
+    }
+
+    //Points before the final one.
+    fn points_needed(&self) -> usize {
+        match self.kind {
+            TransformKind::Move | TransformKind::Copy | TransformKind::Mirror => 1,
+            TransformKind::Rotate | TransformKind::Scale => 2,
+        }
+    }
+
+    //True once the next point finishes the command, which is also when there is a preview.
+    pub fn is_ready(&self) -> bool {
+        self.points.len() >= self.points_needed()
+    }
+
+    pub fn push_point(&mut self, point: Point) {
+        self.points.push(point);
+    }
+
+    pub fn transform(&self, last: Point) -> Option<Transform> {
+        if !self.is_ready() {
+            return None;
+        }
+
+        let base = self.points[0];
+        match self.kind {
+            TransformKind::Move | TransformKind::Copy => {
+                Some(Transform::translation(last.x - base.x, last.y - base.y))
+            }
+            TransformKind::Rotate => {
+                let reference = self.points[1] - base;
+                let target = last - base;
+                if reference.approx_zero() || target.approx_zero() {
+                    return None;
+                }
+                Some(Transform::rotation_about(
+                    base,
+                    target.angle() - reference.angle(),
+                ))
+            }
+            TransformKind::Scale => {
+                let reference = base.distance_to(self.points[1]);
+                if reference < TOLERANCE {
+                    return None;
+                }
+                let factor = base.distance_to(last) / reference;
+                if factor < TOLERANCE {
+                    return None;
+                }
+                Some(Transform::scale_about(base, factor, factor))
+            }
+            TransformKind::Mirror => {
+                if base.approx_eq(last) {
+                    return None;
+                }
+                Some(Transform::mirror(base, last))
+            }
+        }
+    }
+
+    //Rotate and scale can take a typed angle (degrees, counterclockwise) or factor
+    //instead of the reference and target points, once the base point is set.
+    pub fn typed_transform(&self) -> Option<Transform> {
+        let base = *self.points.first()?;
+        let message = match self.kind {
+            TransformKind::Rotate => "Rotation angle (degrees)",
+            TransformKind::Scale => "Scale factor",
+            _ => return None,
+        };
+
+        let value: f64 = web_sys::window()?
+            .prompt_with_message(message)
+            .ok()
+            .flatten()?
+            .trim()
+            .parse()
+            .ok()?;
+        if !value.is_finite() {
+            return None;
+        }
+
+        match self.kind {
+            TransformKind::Rotate => Some(Transform::rotation_about(base, value.to_radians())),
+            TransformKind::Scale if value.abs() >= TOLERANCE => {
+                Some(Transform::scale_about(base, value, value))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::f64::consts::FRAC_PI_2;
+
+use lib::{
+    figure::{
+        leaf::{
+            arc::Arc, bezier::Bezier, block_ref::BlockRef, circle::Circle, ellipse::Ellipse,
+            line::Line, polyline::Polyline, rectangle::Rectangle, spline::Spline, text::Text,
+        },
+        Figure,
+    },
+    geometry::{Point, Vector},
+};
+
+//Points of a figure that can be dragged to reshape it, in figure coordinates.
+//The index of a grip in the returned list is what move_grip takes.
+//Strokes and groups have no grips.
+pub fn grips(figure: &dyn Figure) -> Vec<(f64, f64)> {
+    let figure = figure.as_any();
+    if let Some(line) = figure.downcast_ref::<Line>() {
+        let (start, end) = (line.start(), line.end());
+        vec![start.into(), end.into(), start.midpoint(end).into()]
+    } else if let Some(rectangle) = figure.downcast_ref::<Rectangle>() {
+        rectangle.corners().to_vec()
+    } else if let Some(circle) = figure.downcast_ref::<Circle>() {
+        let center = Point::new(circle.center_x(), circle.center_y());
+        let mut grips = vec![center.into()];
+        for quarter in 0..4 {
+            let direction = Vector::from_angle(FRAC_PI_2 * quarter as f64);
+            grips.push((center + direction * circle.radius().abs()).into());
+        }
+        grips
+    } else if let Some(arc) = figure.downcast_ref::<Arc>() {
+        vec![
+            (arc.center_x(), arc.center_y()),
+            arc.point_at(arc.start_angle()),
+            arc.point_at(arc.end_angle()),
+        ]
+    } else if let Some(ellipse) = figure.downcast_ref::<Ellipse>() {
+        let center = Point::new(ellipse.center_x(), ellipse.center_y());
+        let axis_x = Vector::from_angle(ellipse.rotation());
+        vec![
+            center.into(),
+            (center + axis_x * ellipse.radius_x()).into(),
+            (center + axis_x.perpendicular() * ellipse.radius_y()).into(),
+        ]
+    } else if let Some(polyline) = figure.downcast_ref::<Polyline>() {
+        polyline.vertices().to_vec()
+    } else if let Some(bezier) = figure.downcast_ref::<Bezier>() {
+        let mut grips = vec![bezier.start()];
+        for segment in bezier.segments() {
+            grips.extend([segment.control1, segment.control2, segment.end]);
+        }
+        grips
+    } else if let Some(spline) = figure.downcast_ref::<Spline>() {
+        spline.fit_points().to_vec()
+    } else if let Some(text) = figure.downcast_ref::<Text>() {
+        vec![(text.x(), text.y())]
+    } else if let Some(block_ref) = figure.downcast_ref::<BlockRef>() {
+        vec![(block_ref.insertion_x(), block_ref.insertion_y())]
+    } else {
+        Vec::new()
+    }
+}
+
+//Reshapes the figure so the grip at index ends up at (x, y).
+//Grips that only mark a position, like centers and midpoints, move the whole figure.
+pub fn move_grip(figure: &mut dyn Figure, index: usize, x: f64, y: f64) {
+    let Some(grip) = grips(figure).get(index).copied() else {
+        return;
+    };
+    let figure = figure.as_any_mut();
+
+    if let Some(line) = figure.downcast_mut::<Line>() {
+        match index {
+            0 => {
+                line.set_start_x(x);
+                line.set_start_y(y);
+            }
+            1 => {
+                line.set_end_x(x);
+                line.set_end_y(y);
+            }
+            _ => {
+                let (dx, dy) = (x - grip.0, y - grip.1);
+                line.set_start_x(line.start_x() + dx);
+                line.set_start_y(line.start_y() + dy);
+                line.set_end_x(line.end_x() + dx);
+                line.set_end_y(line.end_y() + dy);
+            }
+        }
+    } else if let Some(rectangle) = figure.downcast_mut::<Rectangle>() {
+        //The opposite corner stays put and becomes the new origin, the sides keep their angle.
+        let opposite: Point = rectangle.corners()[(index + 2) % 4].into();
+        let along = Vector::from_angle(rectangle.rotation());
+        let diagonal = Point::new(x, y) - opposite;
+        rectangle.set_x(opposite.x);
+        rectangle.set_y(opposite.y);
+        rectangle.set_width(diagonal.dot(along));
+        rectangle.set_height(diagonal.dot(along.perpendicular()));
+    } else if let Some(circle) = figure.downcast_mut::<Circle>() {
+        if index == 0 {
+            circle.set_center_x(x);
+            circle.set_center_y(y);
+        } else {
+            circle.set_radius((x - circle.center_x()).hypot(y - circle.center_y()));
+        }
+    } else if let Some(arc) = figure.downcast_mut::<Arc>() {
+        let angle = (y - arc.center_y()).atan2(x - arc.center_x());
+        match index {
+            0 => {
+                arc.set_center_x(x);
+                arc.set_center_y(y);
+            }
+            1 => arc.set_start_angle(angle),
+            _ => arc.set_end_angle(angle),
+        }
+    } else if let Some(ellipse) = figure.downcast_mut::<Ellipse>() {
+        let center = Point::new(ellipse.center_x(), ellipse.center_y());
+        let axis_x = Vector::from_angle(ellipse.rotation());
+        let offset = Point::new(x, y) - center;
+        match index {
+            0 => {
+                ellipse.set_center_x(x);
+                ellipse.set_center_y(y);
+            }
+            1 => ellipse.set_radius_x(offset.dot(axis_x).abs()),
+            _ => ellipse.set_radius_y(offset.dot(axis_x.perpendicular()).abs()),
+        }
+    } else if let Some(polyline) = figure.downcast_mut::<Polyline>() {
+        polyline.set_vertex(index, x, y);
+    } else if let Some(bezier) = figure.downcast_mut::<Bezier>() {
+        if index == 0 {
+            bezier.set_start(x, y);
+        } else {
+            let segment_index = (index - 1) / 3;
+            let mut segment = bezier.segments()[segment_index];
+            match (index - 1) % 3 {
+                0 => segment.control1 = (x, y),
+                1 => segment.control2 = (x, y),
+                _ => segment.end = (x, y),
+            }
+            bezier.set_segment(segment_index, segment);
+        }
+    } else if let Some(spline) = figure.downcast_mut::<Spline>() {
+        spline.set_fit_point(index, x, y);
+    } else if let Some(text) = figure.downcast_mut::<Text>() {
+        text.set_x(x);
+        text.set_y(y);
+    } else if let Some(block_ref) = figure.downcast_mut::<BlockRef>() {
+        block_ref.set_insertion_point(x, y);
+    }
+}
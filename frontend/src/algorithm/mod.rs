@@ -1,3 +1,4 @@
 pub mod coordinates_converter;
 pub mod draw_mode;
+pub mod grip;
 pub mod visitor;
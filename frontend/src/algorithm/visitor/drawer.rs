@@ -1,20 +1,283 @@
-use lib::figure::{leaf::line::Line, Visitor};
+use std::{cell::Cell, collections::HashMap, f64::consts::TAU};
+
+use lib::{
+    common::{Color, FillStyle, HatchPattern, LengthUnit, LineCap, LineJoin, StrokeStyle},
+    figure::{
+        block::{BlockDefinition, BlockName, MAX_BLOCK_DEPTH},
+        leaf::{
+            arc::{Arc, ArcDirection},
+            bezier::{Bezier, BezierSegment},
+            block_ref::BlockRef,
+            circle::Circle,
+            ellipse::Ellipse,
+            line::Line,
+            polyline::Polyline,
+            rectangle::Rectangle,
+            spline::Spline,
+            stroke::Stroke,
+            text::{Text, TextAlignment},
+        },
+        Figure, Visitor,
+    },
+};
+use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, WebGlProgram, WebGlRenderingContext};
 
 use crate::{algorithm::coordinates_converter::convert_figure_to_device, Coordinates};
 
+pub const FONT_FAMILY: &str = "sans-serif";
+
+//Stroke color of selected figures.
+const HIGHLIGHT_COLOR: &str = "#FF8C00";
+
+//Added to the line width of selected figures, in device pixels.
+const HIGHLIGHT_EXTRA_WIDTH: f64 = 1.5;
+
+//Hatch lines closer than this, in device pixels, are drawn as a solid fill instead.
+const MIN_HATCH_SPACING: f64 = 2.0;
+
+//CSS font for a text figure at the current zoom, so text scales along with geometry.
+pub fn text_font(text: &Text, coordinates: &Coordinates) -> String {
+    format!(
+        "{0}px {FONT_FAMILY}",
+        text.font_size() * coordinates.zoom_rate
+    )
+}
+
+//Stroke width in device pixels. Figure unit widths grow and shrink with zoom.
+fn device_width(stroke_style: &StrokeStyle, coordinates: &Coordinates) -> f64 {
+    match stroke_style.unit {
+        LengthUnit::Figure => stroke_style.width * coordinates.zoom_rate,
+        LengthUnit::Screen => stroke_style.width,
+    }
+}
+
+//Smallest axis aligned box around the points, as (min, max) in figure coordinates.
+fn bounds(points: &[(f64, f64)]) -> ((f64, f64), (f64, f64)) {
+    points.iter().fold(
+        ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN)),
+        |(min, max), (x, y)| {
+            (
+                (min.0.min(*x), min.1.min(*y)),
+                (max.0.max(*x), max.1.max(*y)),
+            )
+        },
+    )
+}
+
+pub fn css_color(color: Color) -> String {
+    format!(
+        "rgba({0},{1},{2},{3})",
+        color.r,
+        color.g,
+        color.b,
+        color.a as f64 / 255.0
+    )
+}
+
 pub struct Drawer<'a> {
     context: &'a CanvasRenderingContext2d,
     coordinates: &'a Coordinates,
+    blocks: &'a HashMap<BlockName, BlockDefinition>,
+    block_depth: Cell<usize>,
+    highlighted: Cell<bool>,
 }
 
 impl<'a> Drawer<'a> {
-    pub fn new(context: &'a CanvasRenderingContext2d, coordinates: &'a Coordinates) -> Self {
+    pub fn new(
+        context: &'a CanvasRenderingContext2d,
+        coordinates: &'a Coordinates,
+        blocks: &'a HashMap<BlockName, BlockDefinition>,
+    ) -> Self {
         Self {
             context,
             coordinates,
+            blocks,
+            block_depth: Cell::new(0),
+            highlighted: Cell::new(false),
         }
     }
+
+    //Draws a selected figure in the highlight color, slightly wider than normal.
+    pub fn draw_highlighted(&self, figure: &mut dyn Figure) {
+        self.context.save();
+        self.context.set_stroke_style_str(HIGHLIGHT_COLOR);
+        self.highlighted.set(true);
+        figure.accept(self);
+        self.highlighted.set(false);
+        self.context.restore();
+    }
+}
+
+impl Drawer<'_> {
+    fn apply_stroke_style(&self, stroke_style: &StrokeStyle) {
+        //Dash lengths are in the same unit as the width.
+        let scale = match stroke_style.unit {
+            LengthUnit::Figure => self.coordinates.zoom_rate,
+            LengthUnit::Screen => 1.0,
+        };
+        let dash_pattern = js_sys::Array::new();
+        for length in stroke_style.dash_pattern.iter() {
+            dash_pattern.push(&JsValue::from_f64(length * scale));
+        }
+
+        let line_cap = match stroke_style.line_cap {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        };
+        let line_join = match stroke_style.line_join {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        };
+
+        let mut line_width = device_width(stroke_style, self.coordinates);
+        if self.highlighted.get() {
+            line_width += HIGHLIGHT_EXTRA_WIDTH;
+        }
+
+        self.context.set_line_width(line_width);
+        let _ = self.context.set_line_dash(&dash_pattern);
+        self.context.set_line_cap(line_cap);
+        self.context.set_line_join(line_join);
+    }
+
+    fn trace_path(&self, vertices: &[(f64, f64)], closed: bool) {
+        self.context.begin_path();
+        for (i, (x, y)) in vertices.iter().enumerate() {
+            let (x, y) = convert_figure_to_device(self.coordinates, *x, *y);
+            if i == 0 {
+                self.context.move_to(x, y);
+            } else {
+                self.context.line_to(x, y);
+            }
+        }
+        if closed {
+            self.context.close_path();
+        }
+    }
+
+    fn stroke_path(&self, vertices: &[(f64, f64)], closed: bool, stroke_style: &StrokeStyle) {
+        self.apply_stroke_style(stroke_style);
+        self.trace_path(vertices, closed);
+        self.context.stroke();
+    }
+
+    //Fills the outline made by trace, then strokes it with the current stroke style.
+    //bounds must contain the whole outline, hatch lines are only generated inside it.
+    fn fill_and_stroke(
+        &self,
+        fill_style: &FillStyle,
+        bounds: ((f64, f64), (f64, f64)),
+        trace: impl Fn(),
+    ) {
+        match *fill_style {
+            FillStyle::None => {}
+            FillStyle::Solid(color) => {
+                trace();
+                self.context.save();
+                self.context.set_fill_style_str(&css_color(color));
+                self.context.fill();
+                self.context.restore();
+            }
+            FillStyle::Hatch {
+                pattern,
+                angle,
+                spacing,
+                color,
+            } => {
+                trace();
+                self.context.save();
+                self.context.clip();
+                self.draw_hatch(pattern, angle, spacing, color, bounds);
+                self.context.restore();
+            }
+        }
+
+        trace();
+        self.context.stroke();
+    }
+
+    //Expects the clip path to still be the current path.
+    fn draw_hatch(
+        &self,
+        pattern: HatchPattern,
+        angle: f64,
+        spacing: f64,
+        color: Color,
+        (min, max): ((f64, f64), (f64, f64)),
+    ) {
+        if spacing * self.coordinates.zoom_rate < MIN_HATCH_SPACING {
+            self.context.set_fill_style_str(&css_color(color));
+            self.context.fill();
+            return;
+        }
+
+        self.context.set_stroke_style_str(&css_color(color));
+        self.context.set_line_width(1.0);
+        let _ = self.context.set_line_dash(&js_sys::Array::new());
+
+        let corners = [
+            (min.0, min.1),
+            (max.0, min.1),
+            (max.0, max.1),
+            (min.0, max.1),
+        ];
+        self.context.begin_path();
+        for line_angle in pattern.line_angles() {
+            //Lines run along (cos, sin) and are stepped along the normal (-sin, cos).
+            //Steps are counted from the figure origin, so the pattern lines up across figures.
+            let (sin, cos) = (line_angle + angle).sin_cos();
+            let along = corners.map(|(x, y)| x * cos + y * sin);
+            let across = corners.map(|(x, y)| -x * sin + y * cos);
+            let along_min = along.iter().cloned().fold(f64::MAX, f64::min);
+            let along_max = along.iter().cloned().fold(f64::MIN, f64::max);
+            let across_min = across.iter().cloned().fold(f64::MAX, f64::min);
+            let across_max = across.iter().cloned().fold(f64::MIN, f64::max);
+
+            let first = (across_min / spacing).ceil() as i64;
+            let last = (across_max / spacing).floor() as i64;
+            for step in first..=last {
+                let offset = step as f64 * spacing;
+                let (start_x, start_y) = convert_figure_to_device(
+                    self.coordinates,
+                    along_min * cos - offset * sin,
+                    along_min * sin + offset * cos,
+                );
+                let (end_x, end_y) = convert_figure_to_device(
+                    self.coordinates,
+                    along_max * cos - offset * sin,
+                    along_max * sin + offset * cos,
+                );
+                self.context.move_to(start_x, start_y);
+                self.context.line_to(end_x, end_y);
+            }
+        }
+        self.context.stroke();
+    }
+
+    fn stroke_bezier(
+        &self,
+        start: (f64, f64),
+        segments: &[BezierSegment],
+        stroke_style: &StrokeStyle,
+    ) {
+        self.apply_stroke_style(stroke_style);
+        let convert = |(x, y): (f64, f64)| convert_figure_to_device(self.coordinates, x, y);
+
+        let (start_x, start_y) = convert(start);
+        self.context.begin_path();
+        self.context.move_to(start_x, start_y);
+        for segment in segments {
+            let (control1_x, control1_y) = convert(segment.control1);
+            let (control2_x, control2_y) = convert(segment.control2);
+            let (end_x, end_y) = convert(segment.end);
+            self.context
+                .bezier_curve_to(control1_x, control1_y, control2_x, control2_y, end_x, end_y);
+        }
+        self.context.stroke();
+    }
 }
 
 impl Visitor for Drawer<'_> {
@@ -23,32 +286,225 @@ impl Visitor for Drawer<'_> {
             convert_figure_to_device(self.coordinates, line.start_x(), line.start_y());
         let (end_x, end_y) = convert_figure_to_device(self.coordinates, line.end_x(), line.end_y());
 
+        self.apply_stroke_style(line.stroke_style());
         self.context.begin_path();
         self.context.move_to(start_x, start_y);
         self.context.line_to(end_x, end_y);
         self.context.stroke();
     }
+
+    fn visit_rectangle(&self, rectangle: &mut Rectangle) {
+        let corners = rectangle.corners();
+
+        self.apply_stroke_style(rectangle.stroke_style());
+        self.fill_and_stroke(rectangle.fill_style(), bounds(&corners), || {
+            self.trace_path(&corners, true)
+        });
+    }
+
+    fn visit_circle(&self, circle: &mut Circle) {
+        let (x, y) =
+            convert_figure_to_device(self.coordinates, circle.center_x(), circle.center_y());
+        let radius = circle.radius().abs() * self.coordinates.zoom_rate;
+
+        let figure_radius = circle.radius().abs();
+        let bounds = (
+            (
+                circle.center_x() - figure_radius,
+                circle.center_y() - figure_radius,
+            ),
+            (
+                circle.center_x() + figure_radius,
+                circle.center_y() + figure_radius,
+            ),
+        );
+
+        self.apply_stroke_style(circle.stroke_style());
+        self.fill_and_stroke(circle.fill_style(), bounds, || {
+            self.context.begin_path();
+            let _ = self.context.arc(x, y, radius, 0.0, TAU);
+        });
+    }
+
+    fn visit_arc(&self, arc: &mut Arc) {
+        let (x, y) = convert_figure_to_device(self.coordinates, arc.center_x(), arc.center_y());
+        let radius = arc.radius().abs() * self.coordinates.zoom_rate;
+
+        //The device y axis points down, so angles flip sign
+        //and figure counterclockwise becomes canvas anticlockwise.
+        self.apply_stroke_style(arc.stroke_style());
+        self.context.begin_path();
+        let _ = self.context.arc_with_anticlockwise(
+            x,
+            y,
+            radius,
+            -arc.start_angle(),
+            -arc.end_angle(),
+            arc.direction() == ArcDirection::CounterClockwise,
+        );
+        self.context.stroke();
+    }
+
+    fn visit_ellipse(&self, ellipse: &mut Ellipse) {
+        let (x, y) =
+            convert_figure_to_device(self.coordinates, ellipse.center_x(), ellipse.center_y());
+        let radius_x = ellipse.radius_x().abs() * self.coordinates.zoom_rate;
+        let radius_y = ellipse.radius_y().abs() * self.coordinates.zoom_rate;
+
+        //The larger radius bounds the ellipse at any rotation.
+        let figure_radius = ellipse.radius_x().abs().max(ellipse.radius_y().abs());
+        let bounds = (
+            (
+                ellipse.center_x() - figure_radius,
+                ellipse.center_y() - figure_radius,
+            ),
+            (
+                ellipse.center_x() + figure_radius,
+                ellipse.center_y() + figure_radius,
+            ),
+        );
+
+        self.apply_stroke_style(ellipse.stroke_style());
+        self.fill_and_stroke(ellipse.fill_style(), bounds, || {
+            self.context.begin_path();
+            let _ = self
+                .context
+                .ellipse(x, y, radius_x, radius_y, -ellipse.rotation(), 0.0, TAU);
+        });
+    }
+
+    fn visit_polyline(&self, polyline: &mut Polyline) {
+        //Only a closed polyline has an inside to fill.
+        let fill_style = if polyline.closed() {
+            *polyline.fill_style()
+        } else {
+            FillStyle::None
+        };
+
+        self.apply_stroke_style(polyline.stroke_style());
+        self.fill_and_stroke(&fill_style, bounds(polyline.vertices()), || {
+            self.trace_path(polyline.vertices(), polyline.closed())
+        });
+    }
+
+    fn visit_bezier(&self, bezier: &mut Bezier) {
+        self.stroke_bezier(bezier.start(), bezier.segments(), bezier.stroke_style());
+    }
+
+    fn visit_spline(&self, spline: &mut Spline) {
+        if let Some(start) = spline.fit_points().first() {
+            self.stroke_bezier(*start, &spline.bezier_segments(), spline.stroke_style());
+        }
+    }
+
+    fn visit_text(&self, text: &mut Text) {
+        let (x, y) = convert_figure_to_device(self.coordinates, text.x(), text.y());
+        let align = match text.alignment() {
+            TextAlignment::Left => "left",
+            TextAlignment::Center => "center",
+            TextAlignment::Right => "right",
+        };
+
+        self.context.save();
+        let _ = self.context.translate(x, y);
+        //The device y axis points down, so the rotation flips sign.
+        let _ = self.context.rotate(-text.rotation());
+        self.context.set_font(&text_font(text, self.coordinates));
+        self.context.set_text_align(align);
+        self.context.set_text_baseline("alphabetic");
+        if self.highlighted.get() {
+            self.context.set_fill_style_str(HIGHLIGHT_COLOR);
+        } else {
+            self.context.set_fill_style_str(&css_color(text.color()));
+        }
+        let _ = self.context.fill_text(text.text(), 0.0, 0.0);
+        if let Some(stroke_style) = text.stroke_style() {
+            self.apply_stroke_style(stroke_style);
+            let _ = self.context.stroke_text(text.text(), 0.0, 0.0);
+        }
+        self.context.restore();
+    }
+
+    fn visit_stroke(&self, stroke: &mut Stroke) {
+        self.stroke_path(stroke.points(), false, stroke.stroke_style());
+    }
+
+    //Draws the definition's figures through a canvas transform, so every figure type
+    //is placed, scaled and rotated the same way. Line widths scale along with the instance.
+    fn visit_block_ref(&self, block_ref: &mut BlockRef) {
+        let Some(definition) = self.blocks.get(block_ref.name()) else {
+            return;
+        };
+        if self.block_depth.get() >= MAX_BLOCK_DEPTH {
+            return;
+        }
+
+        //The instance transform works in figure coordinates. Conjugating it with the
+        //figure to device conversion gives the same transform in device coordinates,
+        //where the device y axis points down.
+        let (sin, cos) = block_ref.rotation().sin_cos();
+        let a = cos * block_ref.scale_x();
+        let b = -sin * block_ref.scale_x();
+        let c = sin * block_ref.scale_y();
+        let d = cos * block_ref.scale_y();
+        let (origin_x, origin_y) = convert_figure_to_device(self.coordinates, 0.0, 0.0);
+        let (insertion_x, insertion_y) = convert_figure_to_device(
+            self.coordinates,
+            block_ref.insertion_x(),
+            block_ref.insertion_y(),
+        );
+        let e = insertion_x - (a * origin_x + c * origin_y);
+        let f = insertion_y - (b * origin_x + d * origin_y);
+
+        self.context.save();
+        let _ = self.context.transform(a, b, c, d, e, f);
+        self.block_depth.set(self.block_depth.get() + 1);
+        for data in definition.figures.iter() {
+            let mut figure: Box<dyn Figure> = data.clone().into();
+            figure.accept(self);
+        }
+        self.block_depth.set(self.block_depth.get() - 1);
+        self.context.restore();
+    }
 }
 
 pub struct DrawerGL<'a> {
     gl: &'a WebGlRenderingContext,
     shader_program: &'a WebGlProgram,
+    coordinates: &'a Coordinates,
 }
 
 impl<'a> DrawerGL<'a> {
-    pub fn new(gl: &'a WebGlRenderingContext, shader_program: &'a WebGlProgram) -> Self {
-        Self { gl, shader_program }
+    pub fn new(
+        gl: &'a WebGlRenderingContext,
+        shader_program: &'a WebGlProgram,
+        coordinates: &'a Coordinates,
+    ) -> Self {
+        Self {
+            gl,
+            shader_program,
+            coordinates,
+        }
     }
 }
 
-impl Visitor for DrawerGL<'_> {
-    fn visit_line(&self, line: &mut Line) {
-        let vectices: Vec<f32> = vec![
-            line.start_x() as f32,
-            line.start_y() as f32,
-            line.end_x() as f32,
-            line.end_y() as f32,
-        ];
+impl DrawerGL<'_> {
+    //Most WebGL implementations only support a line width of 1, larger widths are clamped.
+    fn draw_vertices(
+        &self,
+        vertices: &[(f64, f64)],
+        color: Color,
+        stroke_style: &StrokeStyle,
+        mode: u32,
+    ) {
+        self.gl
+            .line_width(device_width(stroke_style, self.coordinates) as f32);
+
+        let mut vectices: Vec<f32> = Vec::new();
+        for (x, y) in vertices {
+            vectices.push(*x as f32);
+            vectices.push(*y as f32);
+        }
         let verts = js_sys::Float32Array::from(vectices.as_slice());
         self.gl.buffer_data_with_array_buffer_view(
             WebGlRenderingContext::ARRAY_BUFFER,
@@ -56,16 +512,153 @@ impl Visitor for DrawerGL<'_> {
             WebGlRenderingContext::STATIC_DRAW,
         );
 
-        let color = self.gl.get_uniform_location(self.shader_program, "color");
-        let rgba = line.color();
+        let location = self.gl.get_uniform_location(self.shader_program, "color");
         self.gl.uniform4f(
-            color.as_ref(),
-            rgba.r as f32 / 255.0,
-            rgba.g as f32 / 255.0,
-            rgba.b as f32 / 255.0,
-            rgba.a as f32 / 255.0,
+            location.as_ref(),
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            color.a as f32 / 255.0,
         );
 
-        self.gl.draw_arrays(WebGlRenderingContext::LINES, 0, 2);
+        self.gl.draw_arrays(mode, 0, vertices.len() as i32);
     }
 }
+
+//Number of segments used to approximate curves, since WebGL can only draw straight lines.
+const CURVE_SEGMENTS: usize = 64;
+
+//Same as CURVE_SEGMENTS but for each cubic segment of a Bézier or spline.
+const BEZIER_SEGMENT_STEPS: usize = 16;
+
+fn flatten_bezier(start: (f64, f64), segments: &[BezierSegment]) -> Vec<(f64, f64)> {
+    let mut vertices = vec![start];
+    let mut current = start;
+    for segment in segments {
+        for i in 1..=BEZIER_SEGMENT_STEPS {
+            vertices.push(segment.point_at(current, i as f64 / BEZIER_SEGMENT_STEPS as f64));
+        }
+        current = segment.end;
+    }
+    vertices
+}
+
+impl Visitor for DrawerGL<'_> {
+    fn visit_line(&self, line: &mut Line) {
+        self.draw_vertices(
+            &[
+                (line.start_x(), line.start_y()),
+                (line.end_x(), line.end_y()),
+            ],
+            line.color(),
+            line.stroke_style(),
+            WebGlRenderingContext::LINES,
+        );
+    }
+
+    fn visit_rectangle(&self, rectangle: &mut Rectangle) {
+        self.draw_vertices(
+            &rectangle.corners(),
+            rectangle.color(),
+            rectangle.stroke_style(),
+            WebGlRenderingContext::LINE_LOOP,
+        );
+    }
+
+    fn visit_circle(&self, circle: &mut Circle) {
+        let vertices: Vec<(f64, f64)> = (0..CURVE_SEGMENTS)
+            .map(|i| {
+                let angle = TAU * i as f64 / CURVE_SEGMENTS as f64;
+                (
+                    circle.center_x() + circle.radius() * angle.cos(),
+                    circle.center_y() + circle.radius() * angle.sin(),
+                )
+            })
+            .collect();
+        self.draw_vertices(
+            &vertices,
+            circle.color(),
+            circle.stroke_style(),
+            WebGlRenderingContext::LINE_LOOP,
+        );
+    }
+
+    fn visit_arc(&self, arc: &mut Arc) {
+        let sweep = match arc.direction() {
+            ArcDirection::CounterClockwise => arc.sweep(),
+            ArcDirection::Clockwise => -arc.sweep(),
+        };
+        let vertices: Vec<(f64, f64)> = (0..=CURVE_SEGMENTS)
+            .map(|i| arc.point_at(arc.start_angle() + sweep * i as f64 / CURVE_SEGMENTS as f64))
+            .collect();
+        self.draw_vertices(
+            &vertices,
+            arc.color(),
+            arc.stroke_style(),
+            WebGlRenderingContext::LINE_STRIP,
+        );
+    }
+
+    fn visit_ellipse(&self, ellipse: &mut Ellipse) {
+        let vertices: Vec<(f64, f64)> = (0..CURVE_SEGMENTS)
+            .map(|i| ellipse.point_at(TAU * i as f64 / CURVE_SEGMENTS as f64))
+            .collect();
+        self.draw_vertices(
+            &vertices,
+            ellipse.color(),
+            ellipse.stroke_style(),
+            WebGlRenderingContext::LINE_LOOP,
+        );
+    }
+
+    fn visit_polyline(&self, polyline: &mut Polyline) {
+        let mode = if polyline.closed() {
+            WebGlRenderingContext::LINE_LOOP
+        } else {
+            WebGlRenderingContext::LINE_STRIP
+        };
+        self.draw_vertices(
+            polyline.vertices(),
+            polyline.color(),
+            polyline.stroke_style(),
+            mode,
+        );
+    }
+
+    fn visit_bezier(&self, bezier: &mut Bezier) {
+        let vertices = flatten_bezier(bezier.start(), bezier.segments());
+        self.draw_vertices(
+            &vertices,
+            bezier.color(),
+            bezier.stroke_style(),
+            WebGlRenderingContext::LINE_STRIP,
+        );
+    }
+
+    fn visit_spline(&self, spline: &mut Spline) {
+        if let Some(start) = spline.fit_points().first() {
+            let vertices = flatten_bezier(*start, &spline.bezier_segments());
+            self.draw_vertices(
+                &vertices,
+                spline.color(),
+                spline.stroke_style(),
+                WebGlRenderingContext::LINE_STRIP,
+            );
+        }
+    }
+
+    //WebGL has no text rendering of its own.
+    fn visit_text(&self, _text: &mut Text) {}
+
+    fn visit_stroke(&self, stroke: &mut Stroke) {
+        self.draw_vertices(
+            stroke.points(),
+            stroke.color(),
+            stroke.stroke_style(),
+            WebGlRenderingContext::LINE_STRIP,
+        );
+    }
+
+    //Block instances are only drawn by Drawer for now.
+    fn visit_block_ref(&self, _block_ref: &mut BlockRef) {}
+}
@@ -1,17 +1,37 @@
-use lib::figure::Figure;
+use lib::figure::{block::BlockDefinition, Figure, FigureId};
 use strum_macros::EnumIter;
 
 #[derive(PartialEq, Copy, Clone, Debug, EnumIter)]
 pub enum DrawModeType {
     SelectMode,
     LineMode,
+    RectangleMode,
+    CircleMode,
+    ThreePointCircleMode,
+    ThreePointArcMode,
+    EllipseMode,
+    PolylineMode,
+    BezierMode,
+    SplineMode,
+    TextMode,
+    FreehandMode,
 }
 
 pub enum ShouldAction {
     Rerender(DrawOption),
     BackToSelect,
     AddFigure(Box<dyn Figure>),
+    AddFigures(Vec<Box<dyn Figure>>),
+    //Replaces the figures with the same ids.
+    UpdateFigures(Vec<Box<dyn Figure>>),
+    DeleteFigures(Vec<FigureId>),
+    //Deletes the figures with the ids and adds the new ones in their place.
+    ReplaceFigures(Vec<FigureId>, Vec<Box<dyn Figure>>),
+    //Defines the block and replaces the figures it was made from with an instance of it.
+    MakeBlock(BlockDefinition, Vec<FigureId>),
     NotifyMousePositionChanged(f64, f64),
+    Undo,
+    Redo,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -1,94 +1,444 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
-use futures::{channel::mpsc::Sender, SinkExt, StreamExt};
+use futures::{
+    channel::mpsc::Sender,
+    future::{select, Either},
+    SinkExt, StreamExt,
+};
+use gloo_timers::future::TimeoutFuture;
 use gloo_utils::errors::JsError;
-use lib::message::{ClientMessage, ServerMessage};
+use lib::{
+    codec::{Codec, Frame},
+    message::{
+        feature, ClientMessage, ErrorCode, RoomId, ServerMessage, UserId, MIN_PROTOCOL_VERSION,
+        PROTOCOL_VERSION,
+    },
+    operation::Sequence,
+};
 use reqwasm::websocket::{futures::WebSocket, Message};
 
 use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
 use yew_agent::Dispatched;
 
 use super::event_bus::{EventBus, EventBusMessage};
 use lib::{IP_ADDRESS, PORT};
 
+//Milliseconds before the first reconnect attempt, doubled after every failed one.
+const INITIAL_RECONNECT_DELAY: u32 = 500;
+const MAX_RECONNECT_DELAY: u32 = 30_000;
+
 #[derive(Debug)]
 pub enum WebSocketError {
     OpenError(JsError),
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConnectionStatus {
+    Connecting,
+    //Heartbeats arrive on time.
+    Connected,
+    //A heartbeat is late.
+    Unstable,
+    //Waiting to reconnect.
+    Disconnected,
+    //The server does not speak this client's protocol.
+    Rejected,
+}
+
+#[derive(Default)]
+struct Connection {
+    on_status: Callback<ConnectionStatus>,
+    //Set while a socket is open.
+    sender: Option<Sender<ClientMessage>>,
+    //Where to join again after reconnecting.
+    joined: Option<(RoomId, UserId)>,
+    last_sequence: Sequence,
+    //Changes made while disconnected, sent once the room is joined again.
+    queue: VecDeque<ClientMessage>,
+    closed: bool,
+    //JSON until the server welcomed us with something else.
+    codec: Codec,
+}
+
+impl Connection {
+    //Changes to the room are kept for later, anything else is only worth sending live.
+    fn queue(&mut self, message: ClientMessage) {
+        match message {
+            ClientMessage::AddFigure(..)
+            | ClientMessage::AddFigures(..)
+            | ClientMessage::UpdateFigure(..)
+            | ClientMessage::UpdateFigures(_)
+            | ClientMessage::DeleteFigures(_)
+            | ClientMessage::ReplaceFigures(..)
+            | ClientMessage::DefineBlock(_) => self.queue.push_back(message),
+            _ => {}
+        }
+    }
+
+    //Puts messages that were sent but never written back in front of the queue.
+    fn requeue(&mut self, unsent: Vec<ClientMessage>) {
+        let queued = std::mem::take(&mut self.queue);
+        for message in unsent {
+            self.queue(message);
+        }
+        self.queue.extend(queued);
+    }
+}
+
+//Keeps the room connection alive. When the socket drops it reconnects with
+//exponential backoff and joins again from the last sequence it received, so the
+//server only sends what was missed.
 #[derive(Clone, Default)]
 pub struct WebsocketService {
-    tx: RefCell<Option<Sender<ClientMessage>>>,
+    connection: Rc<RefCell<Connection>>,
 }
 
 impl WebsocketService {
-    pub fn new() -> Self {
+    pub fn new(on_status: Callback<ConnectionStatus>) -> Self {
+        let connection = Connection {
+            on_status,
+            ..Default::default()
+        };
         Self {
-            tx: RefCell::new(None),
+            connection: Rc::new(RefCell::new(connection)),
         }
     }
 
     pub fn connect(&self) -> Result<(), WebSocketError> {
-        let address = format!("ws:[{IP_ADDRESS}]:{PORT}/websocket");
+        set_status(&self.connection, ConnectionStatus::Connecting);
+        let ws = open()?;
+        spawn_local(run(self.connection.clone(), ws));
+        Ok(())
+    }
 
-        let ws = match WebSocket::open(&address) {
-            Ok(ws) => ws,
-            Err(error) => {
-                return Err(WebSocketError::OpenError(error));
-            }
+    pub fn join(&self, room_id: RoomId, user_id: UserId) {
+        let mut connection = self.connection.borrow_mut();
+        //Sequences and queued changes only mean something in the room they came from.
+        if matches!(&connection.joined, Some((joined_room_id, _)) if *joined_room_id != room_id) {
+            connection.last_sequence = 0;
+            connection.queue.clear();
+        }
+        connection.joined = Some((room_id.clone(), user_id.clone()));
+        let since = connection.last_sequence;
+        //Without a socket yet, joining happens as soon as there is one.
+        if let Some(sender) = connection.sender.as_mut() {
+            let _ = sender.try_send(ClientMessage::Join(room_id, user_id, since));
+        }
+    }
+
+    pub fn disconnect(&self) {
+        let mut connection = self.connection.borrow_mut();
+        connection.closed = true;
+        connection.queue.clear();
+        if let Some(mut sender) = connection.sender.take() {
+            let _ = sender.try_send(ClientMessage::Leave);
+        }
+    }
+
+    //Returns false when the message could not be sent right away. Changes to the
+    //room are queued then and go out after reconnecting.
+    pub fn send(&self, message: ClientMessage) -> bool {
+        let mut connection = self.connection.borrow_mut();
+        let Some(sender) = connection.sender.as_mut() else {
+            connection.queue(message);
+            return false;
         };
 
-        let (mut write, mut read) = ws.split();
+        if let Err(err) = sender.try_send(message) {
+            connection.queue(err.into_inner());
+            return false;
+        }
 
-        let (in_tx, mut in_rx) = futures::channel::mpsc::channel::<ClientMessage>(1000);
+        true
+    }
+}
 
-        let mut event_bus = EventBus::dispatcher();
+fn open() -> Result<WebSocket, WebSocketError> {
+    let address = format!("ws:[{IP_ADDRESS}]:{PORT}/websocket");
+    WebSocket::open(&address).map_err(WebSocketError::OpenError)
+}
 
-        spawn_local(async move {
-            while let Some(message) = in_rx.next().await {
-                let s = serde_json::to_string(&message).unwrap();
-                write.send(Message::Text(s)).await.unwrap();
+async fn run(connection: Rc<RefCell<Connection>>, ws: WebSocket) {
+    let mut ws = Some(ws);
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    loop {
+        if let Some(ws) = ws.take() {
+            if serve(&connection, ws).await {
+                delay = INITIAL_RECONNECT_DELAY;
             }
-        });
-
-        spawn_local(async move {
-            while let Some(message) = read.next().await {
-                match message {
-                    Ok(Message::Text(message)) => {
-                        let message: ServerMessage = serde_json::from_str(&message).unwrap();
-                        event_bus.send(EventBusMessage { message });
-                    }
-                    Ok(Message::Bytes(b)) => {
-                        let decoded = std::str::from_utf8(&b);
-                        if let Ok(_val) = decoded {
-                            //TODO:
-                        }
+        }
+        if connection.borrow().closed {
+            return;
+        }
+
+        set_status(&connection, ConnectionStatus::Disconnected);
+        log::warn!("websocket disconnected, reconnecting in {delay} ms");
+        TimeoutFuture::new(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        if connection.borrow().closed {
+            return;
+        }
+
+        set_status(&connection, ConnectionStatus::Connecting);
+        match open() {
+            Ok(new_ws) => ws = Some(new_ws),
+            Err(err) => log::warn!("websocket open failed {err:?}"),
+        }
+    }
+}
+
+//Runs one socket until it closes. Returns true when the server answered at all,
+//which is what resets the backoff.
+async fn serve(connection: &Rc<RefCell<Connection>>, ws: WebSocket) -> bool {
+    let (mut write, mut read) = ws.split();
+    let (mut in_tx, mut in_rx) = futures::channel::mpsc::channel::<ClientMessage>(1000);
+
+    connection.borrow_mut().codec = Codec::Json;
+    let _ = in_tx.try_send(ClientMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        client_name: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        capabilities: vec![feature::HEARTBEAT.to_string(), feature::BINARY.to_string()],
+    });
+    //Handed to the connection once the server welcomed us.
+    let mut pending = Some(in_tx);
+
+    let write_connection = connection.clone();
+    spawn_local(async move {
+        while let Some(message) = in_rx.next().await {
+            let codec = write_connection.borrow().codec;
+            let frame = match codec.encode(&message) {
+                Ok(Frame::Text(text)) => Message::Text(text),
+                Ok(Frame::Binary(bytes)) => Message::Bytes(bytes),
+                Err(err) => {
+                    log::error!("failed to encode {message:?}: {err}");
+                    continue;
+                }
+            };
+            if write.send(frame).await.is_err() {
+                //What did not make it out goes back to the queue, in order.
+                let mut unsent = vec![message];
+                while let Ok(message) = in_rx.try_recv() {
+                    unsent.push(message);
+                }
+                write_connection.borrow_mut().requeue(unsent);
+                break;
+            }
+        }
+    });
+
+    let mut event_bus = EventBus::dispatcher();
+    let mut answered = false;
+    //Milliseconds the next heartbeat may take before the connection counts as unstable.
+    let mut heartbeat_due: Option<u32> = None;
+    let mut late = false;
+    loop {
+        let next = match heartbeat_due {
+            Some(due) => match select(read.next(), TimeoutFuture::new(due)).await {
+                Either::Left((next, _)) => next,
+                Either::Right(_) => {
+                    if late {
+                        //Two beats missed, the socket is most likely dead without knowing it.
+                        log::warn!("websocket heartbeat lost");
+                        break;
                     }
-                    Err(_e) => {}
+                    late = true;
+                    set_status(connection, ConnectionStatus::Unstable);
+                    continue;
                 }
+            },
+            None => read.next().await,
+        };
+        let Some(message) = next else {
+            break;
+        };
+
+        let frame = match message {
+            Ok(Message::Text(text)) => Frame::Text(text),
+            Ok(Message::Bytes(bytes)) => Frame::Binary(bytes),
+            Err(err) => {
+                log::warn!("websocket error {err}");
+                break;
             }
-        });
+        };
+        if !answered || late {
+            set_status(connection, ConnectionStatus::Connected);
+        }
+        answered = true;
+        late = false;
 
-        *self.tx.borrow_mut() = Some(in_tx);
-        Ok(())
+        let message: ServerMessage = match frame.decode() {
+            Ok(message) => message,
+            Err(err) => {
+                log::error!("invalid server message {err}");
+                continue;
+            }
+        };
+        match &message {
+            ServerMessage::Welcome {
+                protocol_version,
+                server_name,
+                features,
+            } => {
+                if *protocol_version < MIN_PROTOCOL_VERSION {
+                    log::error!("{server_name} speaks protocol version {protocol_version}");
+                    reject(connection);
+                    break;
+                }
+                if let Some(in_tx) = pending.take() {
+                    let mut connection_mut = connection.borrow_mut();
+                    connection_mut.codec = Codec::negotiated(features);
+                    start(&mut connection_mut, in_tx);
+                }
+                continue;
+            }
+            ServerMessage::Error {
+                code: ErrorCode::UnsupportedVersion,
+                message,
+                ..
+            } => {
+                log::error!("server rejected the handshake: {message}");
+                reject(connection);
+                break;
+            }
+            _ => {}
+        }
+        if let ServerMessage::Ping(interval) = message {
+            heartbeat_due = Some((interval as u32).saturating_mul(2));
+            if let Some(sender) = connection.borrow_mut().sender.as_mut() {
+                let _ = sender.try_send(ClientMessage::Pong);
+            }
+            continue;
+        }
+        if !track_sequence(&mut connection.borrow_mut(), &message) {
+            continue;
+        }
+        event_bus.send(EventBusMessage { message });
     }
 
-    pub fn disconnect(&self) {
-        let mut sender = self.tx.borrow_mut().take().unwrap();
-        let _ = sender.try_send(ClientMessage::Leave);
+    connection.borrow_mut().sender = None;
+    answered
+}
+
+//Joins again and sends what was queued, before anything new can go out.
+fn start(connection: &mut Connection, mut in_tx: Sender<ClientMessage>) {
+    if let Some((room_id, user_id)) = connection.joined.clone() {
+        let since = connection.last_sequence;
+        let _ = in_tx.try_send(ClientMessage::Join(room_id, user_id, since));
+        while let Some(message) = connection.queue.pop_front() {
+            if let Err(err) = in_tx.try_send(message) {
+                connection.queue.push_front(err.into_inner());
+                break;
+            }
+        }
     }
+    connection.sender = Some(in_tx);
+}
 
-    pub fn send(&self, message: ClientMessage) -> bool {
-        let sender = self.tx.borrow_mut().take();
-        if sender.is_none() {
-            return false;
+//Reconnecting would only be turned away again.
+fn reject(connection: &Rc<RefCell<Connection>>) {
+    connection.borrow_mut().closed = true;
+    set_status(connection, ConnectionStatus::Rejected);
+}
+
+fn set_status(connection: &Rc<RefCell<Connection>>, status: ConnectionStatus) {
+    let on_status = connection.borrow().on_status.clone();
+    on_status.emit(status);
+}
+
+//Returns false for changes already received, which a rejoin can deliver twice.
+fn track_sequence(connection: &mut Connection, message: &ServerMessage) -> bool {
+    let Some(sequence) = message.sequence() else {
+        return true;
+    };
+
+    //A snapshot replaces everything, even when it is behind what was seen.
+    if !matches!(message, ServerMessage::Snapshot(..)) && sequence <= connection.last_sequence {
+        return false;
+    }
+    connection.last_sequence = sequence;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::figure::FigureId;
+
+    use super::*;
+
+    fn deleted(sequence: Sequence) -> ServerMessage {
+        ServerMessage::FiguresDeleted(sequence, "user".to_string(), Vec::new())
+    }
+
+    fn delete(id: u128) -> ClientMessage {
+        ClientMessage::DeleteFigures(vec![FigureId::from_u128(id)])
+    }
+
+    #[test]
+    fn sequences_already_seen_are_dropped() {
+        let mut connection = Connection::default();
+        assert!(track_sequence(&mut connection, &deleted(1)));
+        assert!(track_sequence(&mut connection, &deleted(2)));
+        assert!(!track_sequence(&mut connection, &deleted(2)));
+        assert!(!track_sequence(&mut connection, &deleted(1)));
+        assert_eq!(connection.last_sequence, 2);
+
+        //Messages without a sequence always go through.
+        assert!(track_sequence(
+            &mut connection,
+            &ServerMessage::UserJoined("user".to_string())
+        ));
+        //So does a snapshot, even one behind what was seen.
+        let snapshot = ServerMessage::Snapshot(1, Vec::new(), Vec::new());
+        assert!(track_sequence(&mut connection, &snapshot));
+        assert_eq!(connection.last_sequence, 1);
+    }
+
+    #[test]
+    fn queue_is_sent_after_join_in_order() {
+        let mut connection = Connection {
+            joined: Some(("room".to_string(), "user".to_string())),
+            last_sequence: 4,
+            ..Default::default()
+        };
+        connection.queue(delete(1));
+        connection.queue(ClientMessage::NotifyMousePositionChanged(0.0, 0.0));
+        connection.queue(delete(2));
+        connection.requeue(vec![delete(0)]);
+
+        let (in_tx, mut in_rx) = futures::channel::mpsc::channel(10);
+        start(&mut connection, in_tx);
+        let mut sent = Vec::new();
+        while let Ok(message) = in_rx.try_recv() {
+            sent.push(message);
         }
+        assert_eq!(
+            sent,
+            vec![
+                ClientMessage::Join("room".to_string(), "user".to_string(), 4),
+                delete(0),
+                delete(1),
+                delete(2),
+            ]
+        );
+        assert!(connection.queue.is_empty());
+        assert!(connection.sender.is_some());
+    }
 
-        let mut sender = sender.unwrap();
-        let _ = sender.try_send(message);
+    #[test]
+    fn joining_another_room_starts_over() {
+        let service = WebsocketService::default();
+        service.join("room".to_string(), "user".to_string());
+        track_sequence(&mut service.connection.borrow_mut(), &deleted(7));
+        service.send(delete(1));
 
-        *self.tx.borrow_mut() = Some(sender);
+        //Joining the same room again keeps both.
+        service.join("room".to_string(), "user".to_string());
+        assert_eq!(service.connection.borrow().last_sequence, 7);
+        assert_eq!(service.connection.borrow().queue.len(), 1);
 
-        true
+        service.join("other".to_string(), "user".to_string());
+        assert_eq!(service.connection.borrow().last_sequence, 0);
+        assert!(service.connection.borrow().queue.is_empty());
     }
 }
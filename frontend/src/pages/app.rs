@@ -94,8 +94,8 @@ fn prevent_wheel_closure() -> Option<Closure<dyn FnMut(web_sys::WheelEvent)>> {
             event.prevent_default();
         });
 
-        let mut option = AddEventListenerOptions::new();
-        option.passive(false);
+        let option = AddEventListenerOptions::new();
+        option.set_passive(false);
 
         window
             .add_event_listener_with_callback_and_add_event_listener_options(
@@ -117,8 +117,8 @@ fn prevent_contextmenu_closure() -> Option<Closure<dyn FnMut(web_sys::MouseEvent
             event.prevent_default();
         });
 
-        let mut option = AddEventListenerOptions::new();
-        option.passive(false);
+        let option = AddEventListenerOptions::new();
+        option.set_passive(false);
 
         window
             .add_event_listener_with_callback_and_add_event_listener_options(
@@ -1,6 +1,16 @@
 use js_sys::Math;
-use lib::{common::Color, figure::Figure};
-use std::{cell::RefCell, rc::Rc};
+use lib::{
+    common::Color,
+    figure::{
+        block::{BlockDefinition, BlockName},
+        Figure, FigureData, FigureId,
+    },
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 use web_sys::CanvasRenderingContext2d;
 
 use crate::{algorithm::coordinates_converter::convert_figure_to_device, Coordinates};
@@ -8,6 +18,8 @@ use crate::{algorithm::coordinates_converter::convert_figure_to_device, Coordina
 #[derive(Default)]
 pub struct FigureList {
     list: Rc<RefCell<Vec<Box<dyn Figure>>>>,
+    //Goes up on every change. The list is shared, so this is what tells renders apart.
+    revision: Cell<u64>,
 }
 
 impl PartialEq for FigureList {
@@ -20,15 +32,60 @@ impl FigureList {
     pub fn new() -> FigureList {
         FigureList {
             list: Rc::new(RefCell::new(Vec::new())),
+            revision: Cell::new(0),
         }
     }
 
     pub fn push(&self, figure: Box<dyn Figure>) {
         self.list.borrow_mut().push(figure);
+        self.bump_revision();
     }
 
     pub fn append(&self, mut figures: Vec<Box<dyn Figure>>) {
         self.list.borrow_mut().append(&mut figures);
+        self.bump_revision();
+    }
+
+    pub fn replace_all(&self, figures: Vec<Box<dyn Figure>>) {
+        *self.list.borrow_mut() = figures;
+        self.bump_revision();
+    }
+
+    //Replaces the figure with the same id in place, keeping its drawing order.
+    pub fn update(&self, figure_id: FigureId, data: FigureData) {
+        if let Some(figure) = self
+            .list
+            .borrow_mut()
+            .iter_mut()
+            .find(|figure| figure.id() == Some(figure_id))
+        {
+            *figure = data.into();
+        }
+        self.bump_revision();
+    }
+
+    pub fn remove(&self, figure_ids: &[FigureId]) {
+        self.list.borrow_mut().retain(|figure| match figure.id() {
+            Some(id) => !figure_ids.contains(&id),
+            None => true,
+        });
+        self.bump_revision();
+    }
+
+    pub fn data(&self, figure_id: FigureId) -> Option<FigureData> {
+        self.list
+            .borrow()
+            .iter()
+            .find(|figure| figure.id() == Some(figure_id))
+            .map(|figure| figure.data())
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.get()
+    }
+
+    fn bump_revision(&self) {
+        self.revision.set(self.revision.get() + 1);
     }
 
     pub fn list(&self) -> Rc<RefCell<Vec<Box<dyn Figure>>>> {
@@ -36,6 +93,59 @@ impl FigureList {
     }
 }
 
+#[derive(Default)]
+pub struct BlockList {
+    list: Rc<RefCell<HashMap<BlockName, BlockDefinition>>>,
+    revision: Cell<u64>,
+}
+
+impl PartialEq for BlockList {
+    fn eq(&self, other: &Self) -> bool {
+        self.list.borrow().len() == other.list.borrow().len()
+    }
+}
+
+impl BlockList {
+    pub fn new() -> BlockList {
+        BlockList {
+            list: Rc::new(RefCell::new(HashMap::new())),
+            revision: Cell::new(0),
+        }
+    }
+
+    //Replaces any definition with the same name, so every instance picks up the change.
+    pub fn insert(&self, definition: BlockDefinition) {
+        self.list
+            .borrow_mut()
+            .insert(definition.name.clone(), definition);
+        self.bump_revision();
+    }
+
+    pub fn append(&self, definitions: Vec<BlockDefinition>) {
+        for definition in definitions {
+            self.insert(definition);
+        }
+    }
+
+    pub fn replace_all(&self, definitions: Vec<BlockDefinition>) {
+        self.list.borrow_mut().clear();
+        self.append(definitions);
+        self.bump_revision();
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.get()
+    }
+
+    fn bump_revision(&self) {
+        self.revision.set(self.revision.get() + 1);
+    }
+
+    pub fn list(&self) -> Rc<RefCell<HashMap<BlockName, BlockDefinition>>> {
+        self.list.clone()
+    }
+}
+
 #[derive(Default)]
 pub struct SharedUsers {
     list: Rc<RefCell<Vec<SharedUser>>>,
@@ -58,7 +168,7 @@ impl SharedUsers {
 
     pub fn push(&self, mut user: SharedUser) {
         let colors = self.personal_color_generator.borrow_mut().generate(1);
-        user.set_color(*colors.get(0).unwrap());
+        user.set_color(*colors.first().unwrap());
         self.list.borrow_mut().push(user);
     }
 
@@ -75,6 +185,18 @@ impl SharedUsers {
         self.list.borrow_mut().append(&mut users);
     }
 
+    pub fn contains(&self, user_id: &str) -> bool {
+        self.list
+            .borrow()
+            .iter()
+            .any(|user| user.user_id == user_id)
+    }
+
+    pub fn clear(&self) {
+        self.list.borrow_mut().clear();
+        *self.personal_color_generator.borrow_mut() = PersonalColorGenerator::new();
+    }
+
     pub fn remove(&self, user_id: String) {
         let position = self
             .list
@@ -146,7 +268,7 @@ impl SharedUser {
         if let Some(mouse_position) = self.mouse_position.as_ref() {
             if let Some(color) = self.color.as_ref() {
                 let color = format!("rgb({0},{1},{2})", color.r, color.g, color.b);
-                context.set_fill_style(&color.into());
+                context.set_fill_style_str(&color);
                 let (x, y) =
                     convert_figure_to_device(coordinates, mouse_position.0, mouse_position.1);
                 context.rect(x, y, 20.0, 20.0);
@@ -180,7 +302,7 @@ impl PersonalColorGenerator {
 
             let color = Color::new(r, g, b, 255);
 
-            if !self.colors.iter().any(|c| *c == color) {
+            if !self.colors.contains(&color) {
                 self.colors.push(color);
                 vec.push(color);
             }
@@ -1,4 +1,6 @@
-use lib::figure::Figure;
+use std::rc::Rc;
+
+use lib::figure::{Figure, FigureId};
 use wasm_bindgen::JsCast;
 use web_sys::{
     CanvasRenderingContext2d, HtmlCanvasElement, WebGlBuffer, WebGlProgram,
@@ -9,6 +11,7 @@ use yew::NodeRef;
 use crate::{
     algorithm::coordinates_converter::convert_device_to_figure,
     base::{DrawOption, ShouldAction},
+    pages::workspace::data::{BlockList, FigureList},
     Coordinates,
 };
 
@@ -17,14 +20,23 @@ pub struct DrawAreaData {
     node_ref: NodeRef,
     coordinates: Coordinates,
     preview: Option<Box<dyn Figure>>,
+    //Helper figures such as control handles, drawn on top of the preview.
+    guides: Vec<Box<dyn Figure>>,
+    figures: Rc<FigureList>,
+    blocks: Rc<BlockList>,
+    selected: Vec<FigureId>,
 }
 
 impl DrawAreaData {
-    pub fn new() -> Self {
+    pub fn new(figures: Rc<FigureList>, blocks: Rc<BlockList>) -> Self {
         Self {
             node_ref: NodeRef::default(),
             coordinates: Coordinates::new(),
             preview: None,
+            guides: Vec::new(),
+            figures,
+            blocks,
+            selected: Vec::new(),
         }
     }
 
@@ -78,6 +90,38 @@ impl DrawAreaData {
         self.preview.take()
     }
 
+    pub fn set_guides(&mut self, guides: Vec<Box<dyn Figure>>) {
+        self.guides = guides;
+    }
+
+    pub fn take_guides(&mut self) -> Vec<Box<dyn Figure>> {
+        std::mem::take(&mut self.guides)
+    }
+
+    pub fn figures(&self) -> &FigureList {
+        &self.figures
+    }
+
+    pub fn blocks(&self) -> &BlockList {
+        &self.blocks
+    }
+
+    pub fn selected(&self) -> &[FigureId] {
+        &self.selected
+    }
+
+    pub fn set_selected(&mut self, selected: Vec<FigureId>) {
+        self.selected = selected;
+    }
+
+    //Drops ids of figures that are no longer in the list, e.g. after someone deleted them.
+    pub fn prune_selected(&mut self) {
+        let list = self.figures.list();
+        let list = list.borrow();
+        self.selected
+            .retain(|id| list.iter().any(|figure| figure.id() == Some(*id)));
+    }
+
     pub fn zoom_in(&mut self, event: WheelEvent) -> Option<ShouldAction> {
         let device_x = event.offset_x() as f64;
         let device_y = event.offset_y() as f64;
@@ -97,7 +141,7 @@ impl DrawAreaData {
             self.coordinates.zoom_rate = 4.0;
         }
 
-        self.coordinates.scroll_v_pos = -1.0 * (self.coordinates.zoom_rate * y) - device_y
+        self.coordinates.scroll_v_pos = -(self.coordinates.zoom_rate * y) - device_y
             + (self.coordinates.center_y * self.coordinates.zoom_rate);
         self.coordinates.scroll_h_pos = self.coordinates.zoom_rate * x - device_x
             + (self.coordinates.center_x * self.coordinates.zoom_rate);
@@ -124,7 +168,7 @@ impl DrawAreaData {
             self.coordinates.zoom_rate = 0.5;
         }
 
-        self.coordinates.scroll_v_pos = -1.0 * (self.coordinates.zoom_rate * y) - device_y
+        self.coordinates.scroll_v_pos = -(self.coordinates.zoom_rate * y) - device_y
             + (self.coordinates.center_y * self.coordinates.zoom_rate);
         self.coordinates.scroll_h_pos = self.coordinates.zoom_rate * x - device_x
             + (self.coordinates.center_x * self.coordinates.zoom_rate);
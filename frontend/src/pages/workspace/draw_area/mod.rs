@@ -1,7 +1,7 @@
 use std::rc::Rc;
 
 use lib::{
-    common::Color,
+    common::{Color, StrokeStyle},
     figure::{leaf::line::Line, Figure},
 };
 use wasm_bindgen::{prelude::Closure, JsCast};
@@ -14,7 +14,8 @@ use yew::{html, Callback, Component, Context, Properties};
 use crate::{
     algorithm::{
         coordinates_converter::{convert_device_to_figure, convert_figure_to_webgl},
-        draw_mode::{pan_mode::PanMode, select_mode::SelectMode, DrawMode},
+        draw_mode::{make_handle, pan_mode::PanMode, select_mode::SelectMode, DrawMode},
+        grip::grips,
         visitor::drawer::{Drawer, DrawerGL},
     },
     base::{DrawModeType, DrawOption, ShouldAction},
@@ -26,7 +27,7 @@ use self::{
 };
 
 use super::{
-    data::{FigureList, SharedUsers},
+    data::{BlockList, FigureList, SharedUsers},
     workspace::ChildRequestType,
     UpdateReason,
 };
@@ -34,6 +35,8 @@ use super::{
 pub mod data;
 pub mod mouse_tracker;
 
+const GUIDE_COLOR: &str = "#0095FF";
+
 pub enum DrawAreaMessage {
     MouseDown(MouseEvent),
     MouseMove(MouseEvent), //This message occurs when mousemove event is triggered.
@@ -50,6 +53,9 @@ pub struct DrawAreaProps {
     pub handler: Callback<ChildRequestType>,
     pub current_mode: DrawModeType,
     pub figures: Rc<FigureList>,
+    pub figures_revision: u64,
+    pub blocks: Rc<BlockList>,
+    pub blocks_revision: u64,
     pub update_reason: Option<UpdateReason>,
     pub shared_users: Rc<SharedUsers>,
 }
@@ -69,7 +75,7 @@ impl Component for DrawArea {
     type Properties = DrawAreaProps;
 
     fn create(ctx: &yew::Context<Self>) -> Self {
-        let data = DrawAreaData::new();
+        let data = DrawAreaData::new(ctx.props().figures.clone(), ctx.props().blocks.clone());
         let current_mode = SelectMode::new();
 
         let keydown_closure = add_keydown_event(ctx);
@@ -103,14 +109,25 @@ impl Component for DrawArea {
                 UpdateReason::ChangeMode => {
                     self.current_mode = ctx.props().current_mode.into();
 
-                    if self.data.take_preview().is_some() {
+                    let had_preview = self.data.take_preview().is_some();
+                    let had_guides = !self.data.take_guides().is_empty();
+
+                    if had_preview || had_guides {
                         self.draw_option = DrawOption::DrawAll;
                     } else {
                         self.draw_option = DrawOption::Remain;
                     }
                     return true;
                 }
-                UpdateReason::FigureAdded | UpdateReason::GetCurrentFigures => {
+                UpdateReason::FiguresDeleted | UpdateReason::SnapshotLoaded => {
+                    self.data.prune_selected();
+                    self.draw_option = DrawOption::DrawAll;
+                    return true;
+                }
+                UpdateReason::FigureAdded
+                | UpdateReason::FigureUpdated
+                | UpdateReason::BlockDefined
+                | UpdateReason::GetCurrentFigures => {
                     self.draw_option = DrawOption::DrawAll;
                     return true;
                 }
@@ -176,8 +193,22 @@ impl Component for DrawArea {
                 }
             }
             DrawAreaMessage::KeyDown(event) => {
-                //Esc key down.
-                if event.key_code() == 27 {
+                let should_action = self
+                    .current_mode
+                    .key_press_event(event.clone(), &mut self.data);
+
+                if should_action.is_some() {
+                    should_action
+                } else if (event.ctrl_key() || event.meta_key()) && event.key_code() == 90 {
+                    //Ctrl + Z or Ctrl + Shift + Z key down.
+                    event.prevent_default();
+                    if event.shift_key() {
+                        Some(ShouldAction::Redo)
+                    } else {
+                        Some(ShouldAction::Undo)
+                    }
+                } else if event.key_code() == 27 {
+                    //Esc key down.
                     if self.current_mode.get_type() != DrawModeType::SelectMode {
                         Some(ShouldAction::BackToSelect)
                     } else {
@@ -222,11 +253,42 @@ impl Component for DrawArea {
                         .handler
                         .emit(ChildRequestType::AddFigure(figure));
                 }
+                ShouldAction::AddFigures(figures) => {
+                    ctx.props()
+                        .handler
+                        .emit(ChildRequestType::AddFigures(figures));
+                }
+                ShouldAction::UpdateFigures(figures) => {
+                    ctx.props()
+                        .handler
+                        .emit(ChildRequestType::UpdateFigures(figures));
+                }
+                ShouldAction::DeleteFigures(figure_ids) => {
+                    ctx.props()
+                        .handler
+                        .emit(ChildRequestType::DeleteFigures(figure_ids));
+                }
+                ShouldAction::ReplaceFigures(figure_ids, figures) => {
+                    ctx.props()
+                        .handler
+                        .emit(ChildRequestType::ReplaceFigures(figure_ids, figures));
+                }
+                ShouldAction::MakeBlock(definition, figure_ids) => {
+                    ctx.props()
+                        .handler
+                        .emit(ChildRequestType::MakeBlock(definition, figure_ids));
+                }
                 ShouldAction::NotifyMousePositionChanged(x, y) => {
                     ctx.props()
                         .handler
                         .emit(ChildRequestType::NotifyMousePositionChanged(x, y));
                 }
+                ShouldAction::Undo => {
+                    ctx.props().handler.emit(ChildRequestType::Undo);
+                }
+                ShouldAction::Redo => {
+                    ctx.props().handler.emit(ChildRequestType::Redo);
+                }
             }
         }
         false
@@ -274,23 +336,63 @@ impl DrawArea {
             canvas.client_height() as f64,
         );
 
-        let preview = self.data.take_preview();
+        let mut preview = self.data.take_preview();
+        let mut guides = self.data.take_guides();
+
+        let blocks = props.blocks.list();
+        let blocks_borrow = blocks.borrow();
 
-        let drawer = Drawer::new(&context, self.data.coordinates());
+        let drawer = Drawer::new(&context, self.data.coordinates(), &blocks_borrow);
 
         let list = props.figures.list();
 
         let mut list_borrow_mut = list.borrow_mut();
 
         for figure in list_borrow_mut.iter_mut() {
-            figure.accept(&drawer);
+            let selected = figure
+                .id()
+                .is_some_and(|id| self.data.selected().contains(&id));
+            if selected {
+                drawer.draw_highlighted(figure.as_mut());
+            } else {
+                figure.accept(&drawer);
+            }
         }
 
-        if let Some(mut preview) = preview {
+        if let Some(preview) = preview.as_mut() {
             preview.accept(&drawer);
-            self.data.set_preview(Some(preview));
         }
 
+        if !guides.is_empty() {
+            context.set_stroke_style_str(GUIDE_COLOR);
+            for guide in guides.iter_mut() {
+                guide.accept(&drawer);
+            }
+            context.set_stroke_style_str("black");
+        }
+
+        let mut grip_handles = Vec::new();
+        for figure in list_borrow_mut.iter() {
+            if figure
+                .id()
+                .is_some_and(|id| self.data.selected().contains(&id))
+            {
+                for (x, y) in grips(figure.as_ref()) {
+                    grip_handles.push(make_handle(x, y, &self.data));
+                }
+            }
+        }
+        if !grip_handles.is_empty() {
+            context.set_stroke_style_str(GUIDE_COLOR);
+            for handle in grip_handles.iter_mut() {
+                handle.accept(&drawer);
+            }
+            context.set_stroke_style_str("black");
+        }
+
+        self.data.set_preview(preview);
+        self.data.set_guides(guides);
+
         let shared_users = props.shared_users.list();
 
         let shared_users_borrow = shared_users.borrow();
@@ -334,9 +436,9 @@ impl DrawArea {
 
         let shader_program = self.webgl_data.as_ref().unwrap().shader_program();
 
-        let drawer = DrawerGL::new(&gl, shader_program);
+        let drawer = DrawerGL::new(&gl, shader_program, self.data.coordinates());
 
-        let mut line = Line::new(start_x, start_y, end_x, end_y, rgba);
+        let mut line = Line::new(start_x, start_y, end_x, end_y, rgba, StrokeStyle::default());
 
         line.accept(&drawer);
     }
@@ -404,6 +506,7 @@ fn canvas_css(draw_area: &DrawArea, current_mode: DrawModeType) -> &'static str
         DrawModeType::SelectMode => {
             "width:100%; height:100%; cursor: url(\"/img/cursor.png\"), auto;"
         }
-        DrawModeType::LineMode => "width:100%; height:100%; cursor: crosshair;",
+        DrawModeType::TextMode => "width:100%; height:100%; cursor: text;",
+        _ => "width:100%; height:100%; cursor: crosshair;",
     }
 }
@@ -43,12 +43,12 @@ impl MouseTracker {
             let current_y = current_y.borrow();
 
             match (*prev_x, *prev_x, *current_x, *current_y) {
-                (Some(_), Some(_), Some(x), Some(y)) => {
-                    if *prev_x != *current_x && *prev_y != *current_y {
-                        *prev_x = *current_x;
-                        *prev_y = *current_y;
-                        link.send_message(DrawAreaMessage::MousePositionChanged(x, y));
-                    }
+                (Some(_), Some(_), Some(x), Some(y))
+                    if *prev_x != *current_x && *prev_y != *current_y =>
+                {
+                    *prev_x = *current_x;
+                    *prev_y = *current_y;
+                    link.send_message(DrawAreaMessage::MousePositionChanged(x, y));
                 }
                 (None, None, Some(x), Some(y)) => {
                     *prev_x = *current_x;
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+
+use lib::{
+    figure::{FigureData, FigureId},
+    message::ClientMessage,
+    operation::RequestId,
+};
+
+//A change made by this user, with enough data to apply it in both directions.
+enum Change {
+    Add(Vec<FigureData>),
+    //Pairs of the data before and after the change.
+    Update(Vec<(FigureData, FigureData)>),
+    Delete(Vec<FigureData>),
+    //The deleted figures and the ones added in their place.
+    Replace(Vec<FigureData>, Vec<FigureData>),
+}
+
+impl Change {
+    fn figure_ids(&self) -> Vec<FigureId> {
+        match self {
+            Change::Add(datas) | Change::Delete(datas) => {
+                datas.iter().filter_map(|data| data.id()).collect()
+            }
+            Change::Update(pairs) => pairs.iter().filter_map(|(before, _)| before.id()).collect(),
+            Change::Replace(deleted, added) => deleted
+                .iter()
+                .chain(added)
+                .filter_map(|data| data.id())
+                .collect(),
+        }
+    }
+
+    fn datas_mut(&mut self) -> Vec<&mut FigureData> {
+        match self {
+            Change::Add(datas) | Change::Delete(datas) => datas.iter_mut().collect(),
+            Change::Update(pairs) => pairs
+                .iter_mut()
+                .flat_map(|(before, after)| [before, after])
+                .collect(),
+            Change::Replace(deleted, added) => deleted.iter_mut().chain(added).collect(),
+        }
+    }
+}
+
+//One undo step.
+struct Entry {
+    serial: u64,
+    changes: Vec<Change>,
+    //Set when a collaborator changed one of the figures afterwards.
+    conflicting: bool,
+}
+
+impl Entry {
+    //The figures of the echo the step was waiting for. A recorded delete turns
+    //into the replace it was sent as.
+    fn added(&mut self, datas: &[FigureData]) {
+        match self.changes.pop() {
+            Some(Change::Delete(deleted)) => {
+                self.changes.push(Change::Replace(deleted, datas.to_vec()))
+            }
+            last => {
+                self.changes.extend(last);
+                self.changes.push(Change::Add(datas.to_vec()));
+            }
+        }
+    }
+}
+
+//What an add echoed back by the server belongs to, looked up by the request id it
+//was sent with. Echoes can come back out of order, live or in the catch-up after
+//reconnecting, and adds that were lost on a dropped connection never come back.
+enum PendingAdd {
+    //Figures of a new action, recorded into the entry with this serial.
+    Record(u64),
+    //Figures put back by undo or redo. They get new ids, which replace the old ones.
+    Restore(Vec<FigureId>),
+}
+
+//Undo and redo of this user's own actions. Collaborators' edits are never rolled back,
+//and steps that touch figures a collaborator changed later are skipped.
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Entry>,
+    redo_stack: Vec<Entry>,
+    pending_adds: HashMap<RequestId, PendingAdd>,
+    next_serial: u64,
+    next_request_id: RequestId,
+}
+
+impl History {
+    //Request ids count up from the first one. It should differ per session, since
+    //another tab or an earlier session of the same user sends echoes with its own ids.
+    pub fn new(first_request_id: RequestId) -> Self {
+        Self {
+            next_request_id: first_request_id,
+            ..Default::default()
+        }
+    }
+
+    //Drops the steps that refer to figures, after the room was replaced by a snapshot.
+    //Steps still waiting for their add are kept, the add may not have been sent yet.
+    pub fn clear(&mut self) {
+        self.redo_stack.clear();
+        self.undo_stack.retain(|entry| entry.changes.is_empty());
+        let serials: Vec<u64> = self.undo_stack.iter().map(|entry| entry.serial).collect();
+        self.pending_adds.retain(|_, pending| match pending {
+            PendingAdd::Record(serial) => serials.contains(serial),
+            PendingAdd::Restore(_) => false,
+        });
+    }
+
+    fn pend(&mut self, pending: PendingAdd) -> RequestId {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending_adds.insert(request_id, pending);
+        request_id
+    }
+
+    fn push_entry(&mut self, changes: Vec<Change>) -> u64 {
+        let serial = self.next_serial;
+        self.next_serial += 1;
+        self.undo_stack.push(Entry {
+            serial,
+            changes,
+            conflicting: false,
+        });
+        self.redo_stack.clear();
+        serial
+    }
+
+    //Call before sending an add, which has to carry the returned request id.
+    //The figure ids are only known once the server echoes it.
+    pub fn record_add(&mut self) -> RequestId {
+        let serial = self.push_entry(Vec::new());
+        self.pend(PendingAdd::Record(serial))
+    }
+
+    pub fn record_update(&mut self, pairs: Vec<(FigureData, FigureData)>) {
+        if !pairs.is_empty() {
+            self.push_entry(vec![Change::Update(pairs)]);
+        }
+    }
+
+    pub fn record_delete(&mut self, deleted: Vec<FigureData>) {
+        if !deleted.is_empty() {
+            self.push_entry(vec![Change::Delete(deleted)]);
+        }
+    }
+
+    //Call before sending a replace, which has to carry the returned request id.
+    pub fn record_replace(&mut self, deleted: Vec<FigureData>) -> RequestId {
+        let serial = self.push_entry(vec![Change::Delete(deleted)]);
+        self.pend(PendingAdd::Record(serial))
+    }
+
+    //Figures this user added or put in place of others, as echoed by the server
+    //with their ids. Returns the
+    //messages to send, which delete them again when their step was undone meanwhile.
+    pub fn own_figures_added(
+        &mut self,
+        request_id: Option<RequestId>,
+        datas: &[FigureData],
+    ) -> Vec<ClientMessage> {
+        let Some(pending) = request_id.and_then(|id| self.pending_adds.remove(&id)) else {
+            //Sent by another session, or already handled.
+            return Vec::new();
+        };
+        match pending {
+            PendingAdd::Record(serial) => {
+                if let Some(entry) = find_entry(&mut self.undo_stack, serial) {
+                    entry.added(datas);
+                } else if let Some(entry) = find_entry(&mut self.redo_stack, serial) {
+                    entry.added(datas);
+                    return vec![delete_message(datas)];
+                }
+            }
+            PendingAdd::Restore(old_ids) => {
+                for (old_id, data) in old_ids.iter().zip(datas) {
+                    if let Some(new_id) = data.id() {
+                        self.replace_id(*old_id, new_id);
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    //A collaborator updated or deleted these figures.
+    pub fn remote_changed(&mut self, figure_ids: &[FigureId]) {
+        for entry in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+            if entry
+                .changes
+                .iter()
+                .any(|change| change.figure_ids().iter().any(|id| figure_ids.contains(id)))
+            {
+                entry.conflicting = true;
+            }
+        }
+    }
+
+    //Returns the messages that roll back the latest step that can still be undone.
+    pub fn undo(&mut self) -> Vec<ClientMessage> {
+        while let Some(entry) = self.undo_stack.pop() {
+            if entry.conflicting {
+                continue;
+            }
+
+            let mut messages = Vec::new();
+            for change in entry.changes.iter().rev() {
+                match change {
+                    Change::Add(datas) => messages.push(delete_message(datas)),
+                    Change::Update(pairs) => {
+                        messages.push(update_message(pairs.iter().map(|(before, _)| before)))
+                    }
+                    Change::Delete(datas) => messages.push(self.restore_message(datas)),
+                    Change::Replace(deleted, added) => {
+                        messages.push(self.replace_message(added, deleted))
+                    }
+                }
+            }
+            self.redo_stack.push(entry);
+            return messages;
+        }
+        Vec::new()
+    }
+
+    pub fn redo(&mut self) -> Vec<ClientMessage> {
+        while let Some(entry) = self.redo_stack.pop() {
+            if entry.conflicting {
+                continue;
+            }
+
+            let mut messages = Vec::new();
+            for change in entry.changes.iter() {
+                match change {
+                    Change::Add(datas) => messages.push(self.restore_message(datas)),
+                    Change::Update(pairs) => {
+                        messages.push(update_message(pairs.iter().map(|(_, after)| after)))
+                    }
+                    Change::Delete(datas) => messages.push(delete_message(datas)),
+                    Change::Replace(deleted, added) => {
+                        messages.push(self.replace_message(deleted, added))
+                    }
+                }
+            }
+            self.undo_stack.push(entry);
+            return messages;
+        }
+        Vec::new()
+    }
+
+    fn restore_message(&mut self, datas: &[FigureData]) -> ClientMessage {
+        let old_ids = datas.iter().filter_map(|data| data.id()).collect();
+        let request_id = self.pend(PendingAdd::Restore(old_ids));
+        ClientMessage::AddFigures(request_id, datas.to_vec())
+    }
+
+    //Puts the restored figures back in place of the removed ones, in one message.
+    fn replace_message(
+        &mut self,
+        removed: &[FigureData],
+        restored: &[FigureData],
+    ) -> ClientMessage {
+        let old_ids = restored.iter().filter_map(|data| data.id()).collect();
+        let request_id = self.pend(PendingAdd::Restore(old_ids));
+        ClientMessage::ReplaceFigures(
+            request_id,
+            removed.iter().filter_map(|data| data.id()).collect(),
+            restored.to_vec(),
+        )
+    }
+
+    fn replace_id(&mut self, old_id: FigureId, new_id: FigureId) {
+        for entry in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+            for change in entry.changes.iter_mut() {
+                for data in change.datas_mut() {
+                    if data.id() == Some(old_id) {
+                        data.set_id(new_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn find_entry(stack: &mut [Entry], serial: u64) -> Option<&mut Entry> {
+    stack.iter_mut().find(|entry| entry.serial == serial)
+}
+
+fn delete_message(datas: &[FigureData]) -> ClientMessage {
+    ClientMessage::DeleteFigures(datas.iter().filter_map(|data| data.id()).collect())
+}
+
+fn update_message<'a>(datas: impl Iterator<Item = &'a FigureData>) -> ClientMessage {
+    ClientMessage::UpdateFigures(
+        datas
+            .filter_map(|data| Some((data.id()?, data.clone())))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::{common::Color, figure::leaf::line::LineData};
+
+    use super::*;
+
+    fn line(id: u128) -> FigureData {
+        FigureData::Line(LineData {
+            id: Some(FigureId::from_u128(id)),
+            start_x: 0.0,
+            start_y: 0.0,
+            end_x: 1.0,
+            end_y: 1.0,
+            color: Color::new(0, 0, 0, 255),
+            stroke_style: Default::default(),
+        })
+    }
+
+    fn deleted(ids: &[u128]) -> Vec<ClientMessage> {
+        vec![ClientMessage::DeleteFigures(
+            ids.iter().map(|id| FigureId::from_u128(*id)).collect(),
+        )]
+    }
+
+    fn added(ids: &[u128]) -> Vec<FigureData> {
+        ids.iter().map(|id| line(*id)).collect()
+    }
+
+    #[test]
+    fn echoed_add_can_be_undone() {
+        let mut history = History::new(0);
+        let request_id = history.record_add();
+        assert!(history
+            .own_figures_added(Some(request_id), &added(&[1]))
+            .is_empty());
+        assert_eq!(history.undo(), deleted(&[1]));
+    }
+
+    #[test]
+    fn add_undone_before_its_echo_is_deleted() {
+        let mut history = History::new(0);
+        let request_id = history.record_add();
+        assert!(history.undo().is_empty());
+
+        assert_eq!(
+            history.own_figures_added(Some(request_id), &added(&[1])),
+            deleted(&[1])
+        );
+        assert_eq!(
+            history.redo(),
+            vec![ClientMessage::AddFigures(1, added(&[1]))]
+        );
+    }
+
+    #[test]
+    fn adds_queued_offline_match_their_own_echoes() {
+        let mut history = History::new(0);
+        let first = history.record_add();
+        let second = history.record_add();
+
+        //Sent after reconnecting, one snapshot of the room may separate the echoes.
+        history.clear();
+        history.own_figures_added(Some(second), &added(&[2]));
+        history.own_figures_added(Some(first), &added(&[1]));
+        assert_eq!(history.undo(), deleted(&[2]));
+        assert_eq!(history.undo(), deleted(&[1]));
+    }
+
+    #[test]
+    fn add_lost_on_a_dropped_connection_takes_no_later_echo() {
+        let mut history = History::new(0);
+        let lost = history.record_add();
+        let queued = history.record_add();
+
+        history.own_figures_added(Some(queued), &added(&[2]));
+        assert_eq!(history.undo(), deleted(&[2]));
+        //The lost add may still turn up in the catch-up.
+        assert!(history.undo().is_empty());
+        assert_eq!(
+            history.own_figures_added(Some(lost), &added(&[1])),
+            deleted(&[1])
+        );
+    }
+
+    #[test]
+    fn catch_up_echoes_of_other_sessions_are_ignored() {
+        let mut history = History::new(100);
+        let request_id = history.record_add();
+
+        //An earlier session of the same user, replayed in the catch-up.
+        history.own_figures_added(Some(0), &added(&[1]));
+        history.own_figures_added(None, &added(&[2]));
+        history.own_figures_added(Some(request_id), &added(&[3]));
+        assert_eq!(history.undo(), deleted(&[3]));
+        assert!(history.undo().is_empty());
+    }
+
+    #[test]
+    fn restore_echoed_after_reconnecting_keeps_the_new_ids() {
+        let mut history = History::new(0);
+        history.record_delete(added(&[1]));
+        let messages = history.undo();
+        let [ClientMessage::AddFigures(request_id, _)] = messages.as_slice() else {
+            panic!("expected a restore, got {messages:?}");
+        };
+
+        history.own_figures_added(Some(*request_id), &added(&[2]));
+        assert_eq!(history.redo(), deleted(&[2]));
+    }
+
+    #[test]
+    fn replace_is_undone_and_redone_in_one_message() {
+        let mut history = History::new(0);
+        let request_id = history.record_replace(added(&[1, 2]));
+        history.own_figures_added(Some(request_id), &added(&[3]));
+
+        let messages = history.undo();
+        let [ClientMessage::ReplaceFigures(restore_id, removed, restored)] = messages.as_slice()
+        else {
+            panic!("expected a replace, got {messages:?}");
+        };
+        assert_eq!(removed, &vec![FigureId::from_u128(3)]);
+        assert_eq!(restored, &added(&[1, 2]));
+
+        //The restored figures come back with new ids.
+        history.own_figures_added(Some(*restore_id), &added(&[4, 5]));
+        let messages = history.redo();
+        let [ClientMessage::ReplaceFigures(_, removed, restored)] = messages.as_slice() else {
+            panic!("expected a replace, got {messages:?}");
+        };
+        assert_eq!(
+            removed,
+            &vec![FigureId::from_u128(4), FigureId::from_u128(5)]
+        );
+        assert_eq!(restored, &added(&[3]));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod chat;
 pub mod data;
 pub mod draw_area;
+pub mod history;
 pub mod title_bar;
 pub mod tool_box;
 #[allow(clippy::module_inception)]
@@ -10,11 +11,16 @@ pub mod workspace;
 pub enum UpdateReason {
     Init,
     FigureAdded,
+    FigureUpdated,
+    FiguresDeleted,
+    BlockDefined,
     GetCurrentFigures,
+    SnapshotLoaded,
     GetCurrentSharedUsers,
     UserJoined,
     UserLeft,
     ShowChat,
     ChangeMode,
     MousePositionChanged,
+    ConnectionStatusChanged,
 }
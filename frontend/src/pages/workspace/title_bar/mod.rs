@@ -1,11 +1,14 @@
 use yew::{html, Callback, Component, Properties};
 
-use crate::pages::workspace::workspace::ChildRequestType;
+use crate::{
+    client::websocket_service::ConnectionStatus, pages::workspace::workspace::ChildRequestType,
+};
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct TitleBarProps {
     pub handler: Callback<ChildRequestType>,
     pub show_chat: bool,
+    pub connection_status: ConnectionStatus,
 }
 
 pub enum TitleBarMessage {}
@@ -33,10 +36,13 @@ impl Component for TitleBar {
             .handler
             .reform(move |_| ChildRequestType::ShowChat(!show_chat));
 
+        let (status_css, status_title) = connection_status_view(ctx.props().connection_status);
+
         html!(
             <div style="height: 100%; overflow: hidden;">
                 <button class="leave_button" onclick={leave_button_clicked}></button>
                 <button class={chat_button_css(show_chat)} onclick={chat_button_clicked}></button>
+                <span class={status_css} title={status_title}></span>
             </div>
         )
     }
@@ -49,3 +55,22 @@ fn chat_button_css(show_chat: bool) -> String {
         String::from("chat_button")
     }
 }
+
+fn connection_status_view(status: ConnectionStatus) -> (&'static str, &'static str) {
+    match status {
+        ConnectionStatus::Connecting => ("connection_status connecting", "Connecting"),
+        ConnectionStatus::Connected => ("connection_status connected", "Connected"),
+        ConnectionStatus::Unstable => (
+            "connection_status unstable",
+            "Connection unstable, waiting for the server",
+        ),
+        ConnectionStatus::Disconnected => (
+            "connection_status disconnected",
+            "Disconnected, reconnecting. Changes are sent once back online",
+        ),
+        ConnectionStatus::Rejected => (
+            "connection_status rejected",
+            "This client version is not supported by the server, reload the page",
+        ),
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{base::DrawModeType, pages::workspace::workspace::ChildRequestType};
+use strum::IntoEnumIterator;
 use yew::{html, Callback, Component, Properties};
 
 #[derive(Clone, PartialEq, Properties)]
@@ -21,38 +22,50 @@ impl Component for ToolBox {
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        let select_button_clicked = ctx
-            .props()
-            .handler
-            .reform(|_| ChildRequestType::ChangeMode(DrawModeType::SelectMode));
+        let current_mode = ctx.props().current_mode;
 
-        let line_button_clicked = ctx
-            .props()
-            .handler
-            .reform(|_| ChildRequestType::ChangeMode(DrawModeType::LineMode));
+        let buttons = DrawModeType::iter().map(|mode| {
+            let clicked = ctx
+                .props()
+                .handler
+                .reform(move |_| ChildRequestType::ChangeMode(mode));
+            let name = button_name(mode);
 
-        let current_mode = ctx.props().current_mode;
+            html! {
+                <button id={format!("{name}_button")} class={ button_class(current_mode, mode) } onclick={clicked}></button>
+            }
+        });
 
         html! {
             <div style="height: 100%; overflow: hidden;">
-                <button id="select_button" class={ select_button_class(current_mode) }  onclick={select_button_clicked}></button>
-                <button id="line_button" class={ line_button_class(current_mode) } onclick={line_button_clicked}></button>
+                { for buttons }
             </div>
         }
     }
 }
 
-fn select_button_class(current_mode: DrawModeType) -> String {
-    format!(
-        "{0} tool_box_select_button",
-        render_tool_button(current_mode, DrawModeType::SelectMode)
-    )
+fn button_name(mode: DrawModeType) -> &'static str {
+    match mode {
+        DrawModeType::SelectMode => "select",
+        DrawModeType::LineMode => "line",
+        DrawModeType::RectangleMode => "rectangle",
+        DrawModeType::CircleMode => "circle",
+        DrawModeType::ThreePointCircleMode => "three_point_circle",
+        DrawModeType::ThreePointArcMode => "three_point_arc",
+        DrawModeType::EllipseMode => "ellipse",
+        DrawModeType::PolylineMode => "polyline",
+        DrawModeType::BezierMode => "bezier",
+        DrawModeType::SplineMode => "spline",
+        DrawModeType::TextMode => "text",
+        DrawModeType::FreehandMode => "freehand",
+    }
 }
 
-fn line_button_class(current_mode: DrawModeType) -> String {
+fn button_class(current_mode: DrawModeType, target_mode: DrawModeType) -> String {
     format!(
-        "{0} tool_box_line_button",
-        render_tool_button(current_mode, DrawModeType::LineMode)
+        "{0} tool_box_{1}_button",
+        render_tool_button(current_mode, target_mode),
+        button_name(target_mode)
     )
 }
 
@@ -1,13 +1,20 @@
 use std::rc::Rc;
 
-use lib::{figure::Figure, message::ServerMessage};
+use lib::{
+    figure::{block::BlockDefinition, leaf::block_ref::BlockRef, Figure, FigureData, FigureId},
+    message::ServerMessage,
+    operation::RequestId,
+};
 use yew::{html, Component, Context, Properties};
 use yew_agent::{Bridge, Bridged};
 use yew_router::scope_ext::RouterScopeExt;
 
 use crate::{
     base::DrawModeType,
-    client::{event_bus::EventBus, websocket_service::WebsocketService},
+    client::{
+        event_bus::EventBus,
+        websocket_service::{ConnectionStatus, WebsocketService},
+    },
     components::login::{Login, LoginNotifyMessage},
     pages::{
         app::{set_user_name, user_name, Route},
@@ -16,7 +23,8 @@ use crate::{
 };
 
 use super::{
-    data::{FigureList, SharedUser, SharedUsers},
+    data::{BlockList, FigureList, SharedUser, SharedUsers},
+    history::History,
     UpdateReason,
 };
 
@@ -25,6 +33,7 @@ pub enum WorkSpaceMessage {
     HandleChildRequest(ChildRequestType),
     RequestInit,
     HandleLoginNotifyMessage(LoginNotifyMessage),
+    ConnectionStatusChanged(ConnectionStatus),
 }
 
 pub enum ChildRequestType {
@@ -32,7 +41,14 @@ pub enum ChildRequestType {
     ShowChat(bool),
     ChangeMode(DrawModeType),
     AddFigure(Box<dyn Figure>),
+    AddFigures(Vec<Box<dyn Figure>>),
+    UpdateFigures(Vec<Box<dyn Figure>>),
+    DeleteFigures(Vec<FigureId>),
+    ReplaceFigures(Vec<FigureId>, Vec<Box<dyn Figure>>),
+    MakeBlock(BlockDefinition, Vec<FigureId>),
     NotifyMousePositionChanged(f64, f64),
+    Undo,
+    Redo,
 }
 
 #[derive(Clone, PartialEq, Properties)]
@@ -46,7 +62,10 @@ pub struct Workspace {
     show_chat: bool,
     current_mode: DrawModeType,
     figures: Rc<FigureList>,
+    blocks: Rc<BlockList>,
     shared_users: Rc<SharedUsers>,
+    history: History,
+    connection_status: ConnectionStatus,
     logined: bool,
     update_reason: Option<UpdateReason>,
 }
@@ -68,7 +87,10 @@ impl Component for Workspace {
             show_chat: false,
             current_mode: DrawModeType::SelectMode,
             figures: Rc::new(FigureList::new()),
+            blocks: Rc::new(BlockList::new()),
             shared_users: Rc::new(SharedUsers::new()),
+            history: History::new(first_request_id()),
+            connection_status: ConnectionStatus::Connecting,
             logined: false,
             update_reason: None,
         }
@@ -110,14 +132,18 @@ impl Workspace {
         let handler_clone = handler.clone();
         let handler_clone2 = handler.clone();
         let figures = self.figures.clone();
+        let figures_revision = self.figures.revision();
+        let blocks = self.blocks.clone();
+        let blocks_revision = self.blocks.revision();
         let update_reason = self.update_reason.clone();
         let shared_users = self.shared_users.clone();
+        let connection_status = self.connection_status;
 
         html! {
             <body>
-                <div class="top"> <TitleBar {handler} {show_chat} /> </div>
+                <div class="top"> <TitleBar {handler} {show_chat} {connection_status} /> </div>
                 <div class="content">
-                    <DrawArea handler = {handler_clone} {current_mode} {figures} {update_reason} {shared_users} />
+                    <DrawArea handler = {handler_clone} {current_mode} {figures} {figures_revision} {blocks} {blocks_revision} {update_reason} {shared_users} />
                     <div class="left"> <ToolBox handler = {handler_clone2} {current_mode} /> </div>
                     if show_chat {
                         <div class="chat_position"> <Chat /> </div>
@@ -129,7 +155,10 @@ impl Workspace {
 }
 
 fn init(ctx: &Context<Workspace>) -> (Option<WebsocketService>, Option<Box<dyn Bridge<EventBus>>>) {
-    let wss = WebsocketService::new();
+    let wss = WebsocketService::new(
+        ctx.link()
+            .callback(WorkSpaceMessage::ConnectionStatusChanged),
+    );
     wss.connect().unwrap();
     let callback = {
         let link = ctx.link().clone();
@@ -153,7 +182,7 @@ fn handle_message(
             workspace.logined = true;
 
             if let Some(wss) = workspace.wss.as_ref() {
-                wss.send(lib::message::ClientMessage::Join(room_id, user_name));
+                wss.join(room_id, user_name);
             }
 
             Some(UpdateReason::Init)
@@ -164,6 +193,14 @@ fn handle_message(
         WorkSpaceMessage::HandleChildRequest(request) => {
             handle_child_request(workspace, ctx, request)
         }
+        WorkSpaceMessage::ConnectionStatusChanged(status) => {
+            if status == workspace.connection_status {
+                None
+            } else {
+                workspace.connection_status = status;
+                Some(UpdateReason::ConnectionStatusChanged)
+            }
+        }
         WorkSpaceMessage::HandleLoginNotifyMessage(msg) => match msg {
             LoginNotifyMessage::EnterRoom(name, _room_id) => {
                 set_user_name(Some(name));
@@ -183,11 +220,79 @@ fn handle_server_message(
     msg: ServerMessage,
 ) -> Option<UpdateReason> {
     let update_reason = match msg {
-        ServerMessage::FigureAdded(data) => {
-            workspace.figures.push(data.into());
+        ServerMessage::FiguresAdded(_sequence, user_id, request_id, datas) => {
+            if is_me(&user_id) {
+                let messages = workspace.history.own_figures_added(request_id, &datas);
+                if let Some(wss) = workspace.wss.as_ref() {
+                    for message in messages {
+                        wss.send(message);
+                    }
+                }
+            }
+            let mut vec = Vec::new();
+            for data in datas {
+                vec.push(data.into());
+            }
+            workspace.figures.append(vec);
             Some(UpdateReason::FigureAdded)
         }
+        ServerMessage::FiguresUpdated(_sequence, user_id, updates) => {
+            if !is_me(&user_id) {
+                let figure_ids: Vec<FigureId> =
+                    updates.iter().map(|(figure_id, _)| *figure_id).collect();
+                workspace.history.remote_changed(&figure_ids);
+            }
+            for (figure_id, data) in updates {
+                workspace.figures.update(figure_id, data);
+            }
+            Some(UpdateReason::FigureUpdated)
+        }
+        ServerMessage::FiguresDeleted(_sequence, user_id, figure_ids) => {
+            if !is_me(&user_id) {
+                workspace.history.remote_changed(&figure_ids);
+            }
+            workspace.figures.remove(&figure_ids);
+            Some(UpdateReason::FiguresDeleted)
+        }
+        ServerMessage::FiguresReplaced(_sequence, user_id, request_id, figure_ids, datas) => {
+            if is_me(&user_id) {
+                let messages = workspace.history.own_figures_added(request_id, &datas);
+                if let Some(wss) = workspace.wss.as_ref() {
+                    for message in messages {
+                        wss.send(message);
+                    }
+                }
+            } else {
+                workspace.history.remote_changed(&figure_ids);
+            }
+            workspace.figures.remove(&figure_ids);
+            workspace
+                .figures
+                .append(datas.into_iter().map(Into::into).collect());
+            Some(UpdateReason::FiguresDeleted)
+        }
+        ServerMessage::BlockDefined(_sequence, definition) => {
+            workspace.blocks.insert(definition);
+            Some(UpdateReason::BlockDefined)
+        }
+        ServerMessage::Snapshot(_sequence, definitions, datas) => {
+            //Too much changed to tell which steps are still valid.
+            workspace.history.clear();
+            workspace.blocks.replace_all(definitions);
+            workspace
+                .figures
+                .replace_all(datas.into_iter().map(Into::into).collect());
+            Some(UpdateReason::SnapshotLoaded)
+        }
         ServerMessage::ResponseInfo(response_type) => match response_type {
+            lib::message::ResponseType::CurrentBlocks(definitions) => {
+                if definitions.is_empty() {
+                    None
+                } else {
+                    workspace.blocks.append(definitions);
+                    Some(UpdateReason::BlockDefined)
+                }
+            }
             lib::message::ResponseType::CurrentFigures(datas) => {
                 if datas.is_empty() {
                     None
@@ -201,6 +306,8 @@ fn handle_server_message(
                 }
             }
             lib::message::ResponseType::CurrentSharedUsers(mut users) => {
+                //The full list, after joining again it replaces what was known.
+                workspace.shared_users.clear();
                 let my_name = user_name().unwrap();
                 if let Some(position) = users.iter().position(|name| *name == my_name) {
                     users.remove(position);
@@ -227,16 +334,17 @@ fn handle_server_message(
         },
         ServerMessage::UserJoined(user_id) => {
             if user_id == user_name().unwrap() {
+                //The figures and blocks come with the join, as the changes since the
+                //last sequence this client has, or a snapshot.
                 if let Some(wss) = workspace.wss.as_ref() {
-                    wss.send(lib::message::ClientMessage::RequestInfo(
-                        lib::message::RequestType::CurrentFigures,
-                    ));
-
                     wss.send(lib::message::ClientMessage::RequestInfo(
                         lib::message::RequestType::CurrentSharedUsers,
                     ));
                 }
                 None
+            } else if workspace.shared_users.contains(&user_id) {
+                //Joined again after a dropped connection.
+                None
             } else {
                 let new_user = SharedUser::new(user_id, false);
                 workspace.shared_users.push(new_user);
@@ -247,6 +355,16 @@ fn handle_server_message(
             workspace.shared_users.remove(user_id);
             Some(UpdateReason::UserLeft)
         }
+        ServerMessage::Error {
+            code,
+            message,
+            in_reply_to,
+        } => {
+            log::warn!("server rejected {in_reply_to:?}: {code:?} {message}");
+            None
+        }
+        //Answered by the websocket service itself.
+        ServerMessage::Welcome { .. } | ServerMessage::Ping(_) => None,
         ServerMessage::NotifyUserMousePositionChanged(user_id, x, y) => {
             workspace
                 .shared_users
@@ -284,7 +402,91 @@ fn handle_child_request(
         ChildRequestType::AddFigure(figure) => {
             let data = figure.data();
             if let Some(wss) = workspace.wss.as_ref() {
-                wss.send(lib::message::ClientMessage::AddFigure(data));
+                let request_id = workspace.history.record_add();
+                wss.send(lib::message::ClientMessage::AddFigure(request_id, data));
+            }
+            None
+        }
+        ChildRequestType::AddFigures(figures) => {
+            let datas = figures.iter().map(|figure| figure.data()).collect();
+            if let Some(wss) = workspace.wss.as_ref() {
+                let request_id = workspace.history.record_add();
+                wss.send(lib::message::ClientMessage::AddFigures(request_id, datas));
+            }
+            None
+        }
+        ChildRequestType::UpdateFigures(figures) => {
+            //Figures that were never stored on the server have nothing to update.
+            let updates: Vec<(FigureId, FigureData)> = figures
+                .iter()
+                .filter_map(|figure| Some((figure.id()?, figure.data())))
+                .collect();
+            let pairs = updates
+                .iter()
+                .filter_map(|(figure_id, data)| {
+                    Some((workspace.figures.data(*figure_id)?, data.clone()))
+                })
+                .collect();
+            if let Some(wss) = workspace.wss.as_ref() {
+                workspace.history.record_update(pairs);
+                wss.send(lib::message::ClientMessage::UpdateFigures(updates));
+            }
+            None
+        }
+        ChildRequestType::DeleteFigures(figure_ids) => {
+            if let Some(wss) = workspace.wss.as_ref() {
+                workspace
+                    .history
+                    .record_delete(current_datas(workspace, &figure_ids));
+                wss.send(lib::message::ClientMessage::DeleteFigures(figure_ids));
+            }
+            None
+        }
+        ChildRequestType::ReplaceFigures(figure_ids, figures) => {
+            let datas = figures.iter().map(|figure| figure.data()).collect();
+            if let Some(wss) = workspace.wss.as_ref() {
+                let request_id = workspace
+                    .history
+                    .record_replace(current_datas(workspace, &figure_ids));
+                wss.send(lib::message::ClientMessage::ReplaceFigures(
+                    request_id, figure_ids, datas,
+                ));
+            }
+            None
+        }
+        ChildRequestType::MakeBlock(definition, figure_ids) => {
+            //The block keeps the figures' coordinates, so an instance at the origin
+            //looks exactly like the figures it replaces.
+            let block_ref = BlockRef::new(definition.name.clone(), 0.0, 0.0, 1.0, 1.0, 0.0);
+            if let Some(wss) = workspace.wss.as_ref() {
+                //Undo puts the figures back but keeps the definition.
+                let request_id = workspace
+                    .history
+                    .record_replace(current_datas(workspace, &figure_ids));
+                wss.send(lib::message::ClientMessage::DefineBlock(definition));
+                wss.send(lib::message::ClientMessage::ReplaceFigures(
+                    request_id,
+                    figure_ids,
+                    vec![block_ref.data()],
+                ));
+            }
+            None
+        }
+        ChildRequestType::Undo => {
+            let messages = workspace.history.undo();
+            if let Some(wss) = workspace.wss.as_ref() {
+                for message in messages {
+                    wss.send(message);
+                }
+            }
+            None
+        }
+        ChildRequestType::Redo => {
+            let messages = workspace.history.redo();
+            if let Some(wss) = workspace.wss.as_ref() {
+                for message in messages {
+                    wss.send(message);
+                }
             }
             None
         }
@@ -300,3 +502,21 @@ fn handle_child_request(
 
     update_reason
 }
+
+fn is_me(user_id: &str) -> bool {
+    user_name().is_some_and(|name| name == user_id)
+}
+
+//Current data of the figures, in drawing order.
+fn current_datas(workspace: &Workspace, figure_ids: &[FigureId]) -> Vec<FigureData> {
+    figure_ids
+        .iter()
+        .filter_map(|figure_id| workspace.figures.data(*figure_id))
+        .collect()
+}
+
+//Random high bits keep the request ids of different sessions of a user apart. Only
+//31 of them, so the ids still fit the signed integers SQLite stores them as.
+fn first_request_id() -> RequestId {
+    ((js_sys::Math::random() * (1u64 << 31) as f64) as RequestId) << 32
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+//How messages are put on the wire once the handshake is done. JSON goes in text
+//frames and is what every peer understands. MessagePack goes in binary frames and
+//is only used when both sides listed message::feature::BINARY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+//An encoded message, sent as a websocket frame of the same kind.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum CodecError {
+    Json(serde_json::Error),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Json(err) => write!(f, "{err}"),
+            CodecError::Encode(err) => write!(f, "{err}"),
+            CodecError::Decode(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(err: serde_json::Error) -> Self {
+        CodecError::Json(err)
+    }
+}
+
+impl From<rmp_serde::encode::Error> for CodecError {
+    fn from(err: rmp_serde::encode::Error) -> Self {
+        CodecError::Encode(err)
+    }
+}
+
+impl From<rmp_serde::decode::Error> for CodecError {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        CodecError::Decode(err)
+    }
+}
+
+impl Codec {
+    //The codec for the features a Welcome settled on.
+    pub fn negotiated(features: &[String]) -> Self {
+        if features
+            .iter()
+            .any(|name| name == crate::message::feature::BINARY)
+        {
+            Codec::MessagePack
+        } else {
+            Codec::Json
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Frame, CodecError> {
+        match self {
+            Codec::Json => Ok(Frame::Text(serde_json::to_string(value)?)),
+            //Named fields keep the encoding readable by other MessagePack tools.
+            Codec::MessagePack => Ok(Frame::Binary(rmp_serde::to_vec_named(value)?)),
+        }
+    }
+}
+
+impl Frame {
+    //The kind of frame tells how it was encoded.
+    pub fn codec(&self) -> Codec {
+        match self {
+            Frame::Text(_) => Codec::Json,
+            Frame::Binary(_) => Codec::MessagePack,
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, CodecError> {
+        match self {
+            Frame::Text(text) => Ok(serde_json::from_str(text)?),
+            Frame::Binary(bytes) => Ok(rmp_serde::from_slice(bytes)?),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Frame::Text(text) => text.len(),
+            Frame::Binary(bytes) => bytes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        common::{Color, FillStyle, StrokeStyle},
+        figure::{
+            block::BlockDefinition,
+            leaf::{
+                line::LineData,
+                rectangle::RectangleData,
+                text::{TextAlignment, TextData},
+            },
+            FigureData,
+        },
+        message::{feature, ClientMessage, ErrorCode, RequestType, ServerMessage},
+    };
+
+    const CODECS: [Codec; 2] = [Codec::Json, Codec::MessagePack];
+
+    fn line(id: Option<Uuid>) -> FigureData {
+        FigureData::Line(LineData {
+            id,
+            start_x: 0.5,
+            start_y: -1.25,
+            end_x: 100.0,
+            end_y: 1e9,
+            color: Color::new(10, 20, 30, 255),
+            stroke_style: Default::default(),
+        })
+    }
+
+    fn rectangle(id: Option<Uuid>) -> FigureData {
+        FigureData::Rectangle(RectangleData {
+            id,
+            x: 1.0,
+            y: 2.0,
+            width: 3.0,
+            height: 4.0,
+            rotation: std::f64::consts::PI,
+            color: Color::new(0, 0, 0, 128),
+            stroke_style: Default::default(),
+            fill_style: FillStyle::default(),
+        })
+    }
+
+    fn text(stroke_style: Option<StrokeStyle>) -> FigureData {
+        FigureData::Text(TextData {
+            id: None,
+            x: 1.0,
+            y: 2.0,
+            text: "text".to_string(),
+            font_size: 12.0,
+            alignment: TextAlignment::Center,
+            rotation: 0.0,
+            color: Color::new(0, 0, 0, 255),
+            stroke_style,
+        })
+    }
+
+    fn client_messages() -> Vec<ClientMessage> {
+        let id = Uuid::from_u128(1);
+        vec![
+            ClientMessage::Hello {
+                protocol_version: 1,
+                client_name: "test".to_string(),
+                capabilities: vec![feature::HEARTBEAT.to_string(), "unknown".to_string()],
+            },
+            ClientMessage::Join("room".to_string(), "user".to_string(), 42),
+            ClientMessage::Leave,
+            ClientMessage::Pong,
+            ClientMessage::AddFigures(3, vec![line(None), rectangle(None)]),
+            ClientMessage::AddFigures(5, vec![text(None), text(Some(StrokeStyle::default()))]),
+            ClientMessage::UpdateFigure(id, line(Some(id))),
+            ClientMessage::DeleteFigures(vec![id, Uuid::from_u128(2)]),
+            ClientMessage::ReplaceFigures(4, vec![id], vec![rectangle(None)]),
+            ClientMessage::DefineBlock(BlockDefinition::new(
+                "block".to_string(),
+                vec![rectangle(None)],
+            )),
+            ClientMessage::RequestInfo(RequestType::CurrentFigures),
+            ClientMessage::NotifyMousePositionChanged(-3.5, 7.0),
+        ]
+    }
+
+    fn server_messages() -> Vec<ServerMessage> {
+        let id = Uuid::from_u128(3);
+        vec![
+            ServerMessage::Welcome {
+                protocol_version: 1,
+                server_name: "test".to_string(),
+                features: vec![feature::BINARY.to_string()],
+            },
+            ServerMessage::UserJoined("user".to_string()),
+            ServerMessage::FiguresAdded(7, "user".to_string(), Some(3), vec![line(Some(id))]),
+            ServerMessage::FiguresDeleted(8, "user".to_string(), vec![id]),
+            ServerMessage::FiguresReplaced(
+                8,
+                "user".to_string(),
+                Some(4),
+                vec![id],
+                vec![rectangle(Some(Uuid::from_u128(5)))],
+            ),
+            ServerMessage::Snapshot(
+                9,
+                vec![BlockDefinition::new(
+                    "block".to_string(),
+                    vec![line(Some(Uuid::from_u128(4)))],
+                )],
+                (0..100)
+                    .map(|n| rectangle(Some(Uuid::from_u128(n))))
+                    .collect(),
+            ),
+            ServerMessage::Ping(15_000),
+            ServerMessage::Error {
+                code: ErrorCode::MalformedMessage,
+                message: "malformed".to_string(),
+                in_reply_to: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        for codec in CODECS {
+            for message in client_messages() {
+                let frame = codec.encode(&message).unwrap();
+                assert_eq!(frame.codec(), codec);
+                let decoded: ClientMessage = frame.decode().unwrap();
+                assert_eq!(decoded, message, "{codec:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        for codec in CODECS {
+            for message in server_messages() {
+                let frame = codec.encode(&message).unwrap();
+                assert_eq!(frame.codec(), codec);
+                let decoded: ServerMessage = frame.decode().unwrap();
+                assert_eq!(decoded, message, "{codec:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn message_pack_is_smaller_for_snapshots() {
+        let snapshot = ServerMessage::Snapshot(
+            1,
+            Vec::new(),
+            (0..1000).map(|n| line(Some(Uuid::from_u128(n)))).collect(),
+        );
+        let json = Codec::Json.encode(&snapshot).unwrap();
+        let binary = Codec::MessagePack.encode(&snapshot).unwrap();
+        assert!(binary.len() < json.len());
+    }
+
+    #[test]
+    fn text_without_stroke_style_is_not_outlined() {
+        let json = r#"{"Text":{"id":null,"x":1.0,"y":2.0,"text":"text","font_size":12.0,
+            "alignment":"Center","rotation":0.0,"color":{"r":0,"g":0,"b":0,"a":255}}}"#;
+        let data: FigureData = serde_json::from_str(json).unwrap();
+        assert_eq!(data, text(None));
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Frame::Text("{\"Join\":1}".to_string())
+            .decode::<ClientMessage>()
+            .is_err());
+        assert!(Frame::Binary(vec![0xc1, 0xff, 0x00])
+            .decode::<ClientMessage>()
+            .is_err());
+    }
+
+    #[test]
+    fn negotiated_codec() {
+        assert_eq!(Codec::negotiated(&[]), Codec::Json);
+        let features = [feature::HEARTBEAT.to_string(), feature::BINARY.to_string()];
+        assert_eq!(Codec::negotiated(&features), Codec::MessagePack);
+    }
+}
@@ -13,3 +13,99 @@ impl Color {
         Color { r, g, b, a }
     }
 }
+
+//Whether a length is in figure units, so it scales with zoom, or in screen pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Figure,
+    Screen,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+//How the outline of a figure is drawn.
+//An empty dash_pattern means a solid line. Dash lengths use the same unit as width.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub unit: LengthUnit,
+    pub dash_pattern: Vec<f64>,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            unit: LengthUnit::Screen,
+            dash_pattern: Vec::new(),
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+        }
+    }
+}
+
+impl StrokeStyle {
+    pub fn new(
+        width: f64,
+        unit: LengthUnit,
+        dash_pattern: Vec<f64>,
+        line_cap: LineCap,
+        line_join: LineJoin,
+    ) -> Self {
+        Self {
+            width,
+            unit,
+            dash_pattern,
+            line_cap,
+            line_join,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum HatchPattern {
+    //Single family of parallel lines at 45 degrees, like the ANSI31 pattern.
+    Ansi31,
+    //Two families of parallel lines crossing at right angles.
+    Cross,
+}
+
+impl HatchPattern {
+    //Angles of the line families in radians, before the hatch angle is added.
+    pub fn line_angles(&self) -> &'static [f64] {
+        match self {
+            HatchPattern::Ansi31 => &[std::f64::consts::FRAC_PI_4],
+            HatchPattern::Cross => &[0.0, std::f64::consts::FRAC_PI_2],
+        }
+    }
+}
+
+//How the inside of a closed figure is filled.
+//Hatch angle is in radians and added to the pattern's own angle,
+//spacing is the distance between lines in figure units.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillStyle {
+    #[default]
+    None,
+    Solid(Color),
+    Hatch {
+        pattern: HatchPattern,
+        angle: f64,
+        spacing: f64,
+        color: Color,
+    },
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use super::FigureData;
+
+pub type BlockName = String;
+
+//Blocks nested deeper than this are not followed, which also stops a block that contains itself.
+pub const MAX_BLOCK_DEPTH: usize = 16;
+
+//Figures stored once per room and drawn wherever a BlockRef points at the name.
+//Figure coordinates are relative to the block's base point at (0, 0).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockDefinition {
+    pub name: BlockName,
+    pub figures: Vec<FigureData>,
+}
+
+impl BlockDefinition {
+    pub fn new(name: BlockName, figures: Vec<FigureData>) -> Self {
+        Self { name, figures }
+    }
+}
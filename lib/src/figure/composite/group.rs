@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figure::{Figure, FigureData, FigureId, Visitor},
+    geometry::{BoundingBox, Point, Transform},
+};
+
+use super::Composite;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GroupData {
+    pub id: Option<FigureId>,
+    pub children: Vec<FigureData>,
+}
+
+//Owns its children so an assembly can be moved and copied as one figure.
+pub struct Group {
+    id: Option<FigureId>,
+    children: Vec<Box<dyn Figure>>,
+}
+
+impl Composite for Group {}
+
+impl Figure for Group {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        for child in self.children.iter_mut() {
+            child.accept(visitor);
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Group(GroupData {
+            id: self.id,
+            children: self.children.iter().map(|child| child.data()).collect(),
+        })
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.children
+            .iter()
+            .filter_map(|child| child.bounding_box())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        self.children
+            .iter()
+            .map(|child| child.distance_to(point))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn transform(&mut self, transform: &Transform) {
+        for child in self.children.iter_mut() {
+            child.transform(transform);
+        }
+    }
+}
+
+impl Clone for Group {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            children: self
+                .children
+                .iter()
+                .map(|child| child.data().into())
+                .collect(),
+        }
+    }
+}
+
+impl Group {
+    pub fn new(children: Vec<Box<dyn Figure>>) -> Self {
+        Self { id: None, children }
+    }
+
+    pub fn children(&self) -> &[Box<dyn Figure>] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut Vec<Box<dyn Figure>> {
+        &mut self.children
+    }
+
+    pub fn into_children(self) -> Vec<Box<dyn Figure>> {
+        self.children
+    }
+}
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use super::Figure;
 
+pub mod group;
+
 pub trait Composite: Figure {}
 
 pub fn upcast(obj: Arc<dyn Composite>) -> Arc<dyn Figure> {
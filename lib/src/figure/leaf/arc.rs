@@ -0,0 +1,325 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
+};
+
+use super::{circle::circumcenter, Leaf};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ArcDirection {
+    CounterClockwise,
+    Clockwise,
+}
+
+//Angles are in radians, measured counterclockwise from the positive x axis in figure coordinates.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArcData {
+    pub id: Option<FigureId>,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub radius: f64,
+    pub start_angle: f64,
+    pub end_angle: f64,
+    pub direction: ArcDirection,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arc {
+    data: ArcData,
+}
+
+impl Leaf for Arc {}
+
+impl Figure for Arc {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_arc(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Arc(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let start_angle = self.data.start_angle;
+        let sweep = self.signed_sweep();
+        let mut bounding_box = BoundingBox::new(
+            self.point_at(start_angle).into(),
+            self.point_at(start_angle + sweep).into(),
+        );
+
+        //The arc reaches further wherever it crosses one of the axes through its center.
+        for quarter in 0..4 {
+            let angle = FRAC_PI_2 * quarter as f64;
+            if distance::angle_in_sweep(angle, start_angle, sweep) {
+                bounding_box = bounding_box.include(self.point_at(angle).into());
+            }
+        }
+
+        Some(bounding_box)
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        distance::point_to_arc(
+            point,
+            Point::new(self.data.center_x, self.data.center_y),
+            self.data.radius.abs(),
+            self.data.start_angle,
+            self.signed_sweep(),
+        )
+    }
+
+    //The end points are mapped and the angles measured again.
+    //A mirror reverses the direction.
+    fn transform(&mut self, transform: &Transform) {
+        let start = transform.apply(self.point_at(self.data.start_angle).into());
+        let end = transform.apply(self.point_at(self.data.end_angle).into());
+        let center = transform.apply(Point::new(self.data.center_x, self.data.center_y));
+
+        self.data.center_x = center.x;
+        self.data.center_y = center.y;
+        self.data.radius *= transform.scale_factor();
+        self.data.start_angle = (start - center).angle();
+        self.data.end_angle = (end - center).angle();
+        if transform.is_mirroring() {
+            self.data.direction = match self.data.direction {
+                ArcDirection::CounterClockwise => ArcDirection::Clockwise,
+                ArcDirection::Clockwise => ArcDirection::CounterClockwise,
+            };
+        }
+    }
+}
+
+impl Arc {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        center_x: f64,
+        center_y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+        direction: ArcDirection,
+        color: Color,
+        stroke_style: StrokeStyle,
+    ) -> Self {
+        let data = ArcData {
+            id: None,
+            center_x,
+            center_y,
+            radius,
+            start_angle,
+            end_angle,
+            direction,
+            color,
+            stroke_style,
+        };
+
+        Self { data }
+    }
+
+    //Arc starting at first, passing through second and ending at third.
+    //Returns None when the three points are on one line.
+    pub fn from_three_points(
+        first: (f64, f64),
+        second: (f64, f64),
+        third: (f64, f64),
+        color: Color,
+        stroke_style: StrokeStyle,
+    ) -> Option<Self> {
+        let (center_x, center_y) = circumcenter(first, second, third)?;
+        let radius = (first.0 - center_x).hypot(first.1 - center_y);
+
+        let start_angle = (first.1 - center_y).atan2(first.0 - center_x);
+        let end_angle = (third.1 - center_y).atan2(third.0 - center_x);
+
+        let cross =
+            (second.0 - first.0) * (third.1 - first.1) - (second.1 - first.1) * (third.0 - first.0);
+        let direction = if cross > 0.0 {
+            ArcDirection::CounterClockwise
+        } else {
+            ArcDirection::Clockwise
+        };
+
+        Some(Self::new(
+            center_x,
+            center_y,
+            radius,
+            start_angle,
+            end_angle,
+            direction,
+            color,
+            stroke_style,
+        ))
+    }
+
+    pub fn center_x(&self) -> f64 {
+        self.data.center_x
+    }
+
+    pub fn center_y(&self) -> f64 {
+        self.data.center_y
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.data.radius
+    }
+
+    pub fn start_angle(&self) -> f64 {
+        self.data.start_angle
+    }
+
+    pub fn end_angle(&self) -> f64 {
+        self.data.end_angle
+    }
+
+    pub fn direction(&self) -> ArcDirection {
+        self.data.direction
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn set_center_x(&mut self, center_x: f64) {
+        self.data.center_x = center_x;
+    }
+
+    pub fn set_center_y(&mut self, center_y: f64) {
+        self.data.center_y = center_y;
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.data.radius = radius;
+    }
+
+    pub fn set_start_angle(&mut self, start_angle: f64) {
+        self.data.start_angle = start_angle;
+    }
+
+    pub fn set_end_angle(&mut self, end_angle: f64) {
+        self.data.end_angle = end_angle;
+    }
+
+    pub fn set_direction(&mut self, direction: ArcDirection) {
+        self.data.direction = direction;
+    }
+
+    //Angle travelled from start to end in the arc's direction, always in [0, 2π).
+    pub fn sweep(&self) -> f64 {
+        let sweep = match self.data.direction {
+            ArcDirection::CounterClockwise => self.data.end_angle - self.data.start_angle,
+            ArcDirection::Clockwise => self.data.start_angle - self.data.end_angle,
+        };
+        sweep.rem_euclid(TAU)
+    }
+
+    //sweep() with a sign, negative for clockwise arcs.
+    pub fn signed_sweep(&self) -> f64 {
+        match self.data.direction {
+            ArcDirection::CounterClockwise => self.sweep(),
+            ArcDirection::Clockwise => -self.sweep(),
+        }
+    }
+
+    pub fn point_at(&self, angle: f64) -> (f64, f64) {
+        (
+            self.data.center_x + self.data.radius * angle.cos(),
+            self.data.center_y + self.data.radius * angle.sin(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::*;
+    use crate::geometry::approx_eq;
+
+    fn quarter_arc() -> Arc {
+        //From (3, 2) counterclockwise to (2, 3).
+        Arc::new(
+            2.0,
+            2.0,
+            1.0,
+            0.0,
+            FRAC_PI_2,
+            ArcDirection::CounterClockwise,
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        )
+    }
+
+    #[test]
+    fn mirror_flips_the_direction() {
+        let mut arc = quarter_arc();
+        //Across the horizontal line through the center.
+        arc.transform(&Transform::mirror(
+            Point::new(0.0, 2.0),
+            Point::new(1.0, 2.0),
+        ));
+
+        assert_eq!(arc.direction(), ArcDirection::Clockwise);
+        assert!(approx_eq(arc.signed_sweep(), -FRAC_PI_2));
+        //The mirrored arc runs below the center, where the original never was.
+        let below = Point::new(2.0 + FRAC_PI_4.cos(), 2.0 - FRAC_PI_4.sin());
+        let above = Point::new(2.0 + FRAC_PI_4.cos(), 2.0 + FRAC_PI_4.sin());
+        assert!(approx_eq(arc.distance_to(below), 0.0));
+        assert!(arc.distance_to(above) > 0.5);
+
+        let bounding_box = arc.bounding_box().unwrap();
+        assert!(bounding_box.min.approx_eq(Point::new(2.0, 1.0)));
+        assert!(bounding_box.max.approx_eq(Point::new(3.0, 2.0)));
+    }
+
+    #[test]
+    fn mirror_twice_restores_the_arc() {
+        let mut arc = quarter_arc();
+        let mirror = Transform::mirror(Point::new(-1.0, 0.0), Point::new(4.0, 3.0));
+        arc.transform(&mirror);
+        arc.transform(&mirror);
+
+        assert_eq!(arc.direction(), ArcDirection::CounterClockwise);
+        assert!(approx_eq(arc.signed_sweep(), FRAC_PI_2));
+        let start: Point = arc.point_at(arc.data.start_angle).into();
+        assert!(start.approx_eq(Point::new(3.0, 2.0)));
+    }
+
+    #[test]
+    fn rotation_keeps_the_direction() {
+        let mut arc = quarter_arc();
+        arc.transform(&Transform::rotation_about(Point::new(0.0, 0.0), 1.0));
+        assert_eq!(arc.direction(), ArcDirection::CounterClockwise);
+        assert!(approx_eq(arc.signed_sweep(), FRAC_PI_2));
+    }
+}
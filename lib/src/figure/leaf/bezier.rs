@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
+};
+
+use super::Leaf;
+
+//One cubic segment. It starts where the previous segment ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BezierSegment {
+    pub control1: (f64, f64),
+    pub control2: (f64, f64),
+    pub end: (f64, f64),
+}
+
+impl BezierSegment {
+    pub fn new(control1: (f64, f64), control2: (f64, f64), end: (f64, f64)) -> Self {
+        Self {
+            control1,
+            control2,
+            end,
+        }
+    }
+
+    //Point at t in [0, 1] for the segment starting at start.
+    pub fn point_at(&self, start: (f64, f64), t: f64) -> (f64, f64) {
+        let u = 1.0 - t;
+        let a = u * u * u;
+        let b = 3.0 * u * u * t;
+        let c = 3.0 * u * t * t;
+        let d = t * t * t;
+        (
+            a * start.0 + b * self.control1.0 + c * self.control2.0 + d * self.end.0,
+            a * start.1 + b * self.control1.1 + c * self.control2.1 + d * self.end.1,
+        )
+    }
+
+    pub fn transform(&mut self, transform: &Transform) {
+        self.control1 = transform.apply(self.control1.into()).into();
+        self.control2 = transform.apply(self.control2.into()).into();
+        self.end = transform.apply(self.end.into()).into();
+    }
+}
+
+//Shared with Spline, which is drawn as a chain of cubic segments too.
+pub(crate) fn chain_bounding_box(start: (f64, f64), segments: &[BezierSegment]) -> BoundingBox {
+    let mut bounding_box = BoundingBox::from_point(start.into());
+    let mut current = start;
+    for segment in segments {
+        bounding_box = bounding_box.union(&BoundingBox::from_cubic_bezier(
+            current.into(),
+            segment.control1.into(),
+            segment.control2.into(),
+            segment.end.into(),
+        ));
+        current = segment.end;
+    }
+    bounding_box
+}
+
+pub(crate) fn chain_distance(point: Point, start: (f64, f64), segments: &[BezierSegment]) -> f64 {
+    let mut nearest = point.distance_to(start.into());
+    let mut current = start;
+    for segment in segments {
+        nearest = nearest.min(distance::point_to_cubic_bezier(
+            point,
+            current.into(),
+            segment.control1.into(),
+            segment.control2.into(),
+            segment.end.into(),
+        ));
+        current = segment.end;
+    }
+    nearest
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BezierData {
+    pub id: Option<FigureId>,
+    pub start: (f64, f64),
+    pub segments: Vec<BezierSegment>,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bezier {
+    data: BezierData,
+}
+
+impl Leaf for Bezier {}
+
+impl Figure for Bezier {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_bezier(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Bezier(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(chain_bounding_box(self.data.start, &self.data.segments))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        chain_distance(point, self.data.start, &self.data.segments)
+    }
+
+    //Bézier curves are affine invariant, so mapping the control points is exact.
+    fn transform(&mut self, transform: &Transform) {
+        self.data.start = transform.apply(self.data.start.into()).into();
+        for segment in self.data.segments.iter_mut() {
+            segment.transform(transform);
+        }
+    }
+}
+
+impl Bezier {
+    pub fn new(
+        start: (f64, f64),
+        segments: Vec<BezierSegment>,
+        color: Color,
+        stroke_style: StrokeStyle,
+    ) -> Self {
+        let data = BezierData {
+            id: None,
+            start,
+            segments,
+            color,
+            stroke_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn start(&self) -> (f64, f64) {
+        self.data.start
+    }
+
+    pub fn segments(&self) -> &[BezierSegment] {
+        &self.data.segments
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn set_start(&mut self, x: f64, y: f64) {
+        self.data.start = (x, y);
+    }
+
+    pub fn push_segment(&mut self, segment: BezierSegment) {
+        self.data.segments.push(segment);
+    }
+
+    pub fn set_segment(&mut self, index: usize, segment: BezierSegment) {
+        if let Some(target) = self.data.segments.get_mut(index) {
+            *target = segment;
+        }
+    }
+
+    //Start point of every segment, in the same order as segments().
+    pub fn segment_starts(&self) -> Vec<(f64, f64)> {
+        let mut starts = Vec::with_capacity(self.data.segments.len());
+        let mut current = self.data.start;
+        for segment in self.data.segments.iter() {
+            starts.push(current);
+            current = segment.end;
+        }
+        starts
+    }
+}
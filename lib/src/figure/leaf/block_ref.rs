@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figure::{block::BlockName, Figure, FigureId, Visitor},
+    geometry::{BoundingBox, Point, Transform, Vector},
+};
+
+use super::Leaf;
+
+//An instance of a block definition. The base point of the block is placed at the
+//insertion point, then the block is scaled and rotated (radians, counterclockwise) around it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockRefData {
+    pub id: Option<FigureId>,
+    pub name: BlockName,
+    pub insertion_x: f64,
+    pub insertion_y: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub rotation: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockRef {
+    data: BlockRefData,
+}
+
+impl Leaf for BlockRef {}
+
+impl Figure for BlockRef {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_block_ref(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::BlockRef(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    //Without the definition only the insertion point is known. Callers that have the
+    //blocks measure the definition's figures through instance_transform instead.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(BoundingBox::from_point(self.insertion_point()))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        point.distance_to(self.insertion_point())
+    }
+
+    //Exact unless the transform shears the instance.
+    fn transform(&mut self, transform: &Transform) {
+        let insertion = transform.apply(self.insertion_point());
+        let axis_x = transform.apply_vector(Vector::from_angle(self.data.rotation));
+        let axis_y = transform.apply_vector(Vector::from_angle(self.data.rotation).perpendicular());
+        let Some(direction_x) = axis_x.normalize() else {
+            return;
+        };
+
+        self.data.insertion_x = insertion.x;
+        self.data.insertion_y = insertion.y;
+        self.data.rotation = direction_x.angle();
+        self.data.scale_x *= axis_x.length();
+        self.data.scale_y *= axis_y.dot(direction_x.perpendicular());
+    }
+}
+
+impl BlockRef {
+    pub fn new(
+        name: BlockName,
+        insertion_x: f64,
+        insertion_y: f64,
+        scale_x: f64,
+        scale_y: f64,
+        rotation: f64,
+    ) -> Self {
+        let data = BlockRefData {
+            id: None,
+            name,
+            insertion_x,
+            insertion_y,
+            scale_x,
+            scale_y,
+            rotation,
+        };
+
+        Self { data }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.data.name
+    }
+
+    pub fn insertion_x(&self) -> f64 {
+        self.data.insertion_x
+    }
+
+    pub fn insertion_y(&self) -> f64 {
+        self.data.insertion_y
+    }
+
+    pub fn scale_x(&self) -> f64 {
+        self.data.scale_x
+    }
+
+    pub fn scale_y(&self) -> f64 {
+        self.data.scale_y
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.data.rotation
+    }
+
+    pub fn set_insertion_point(&mut self, x: f64, y: f64) {
+        self.data.insertion_x = x;
+        self.data.insertion_y = y;
+    }
+
+    pub fn set_scale(&mut self, scale_x: f64, scale_y: f64) {
+        self.data.scale_x = scale_x;
+        self.data.scale_y = scale_y;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.data.rotation = rotation;
+    }
+
+    pub fn insertion_point(&self) -> Point {
+        Point::new(self.data.insertion_x, self.data.insertion_y)
+    }
+
+    //Maps the definition's coordinates to this instance's place in the drawing.
+    pub fn instance_transform(&self) -> Transform {
+        Transform::scale(self.data.scale_x, self.data.scale_y)
+            .then(&Transform::rotation(self.data.rotation))
+            .then(&Transform::translation(
+                self.data.insertion_x,
+                self.data.insertion_y,
+            ))
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
+};
+
+use super::Leaf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CircleData {
+    pub id: Option<FigureId>,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub radius: f64,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+    pub fill_style: FillStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Circle {
+    data: CircleData,
+}
+
+impl Leaf for Circle {}
+
+impl Figure for Circle {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_circle(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Circle(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let radius = self.data.radius.abs();
+        Some(BoundingBox::new(
+            Point::new(self.data.center_x - radius, self.data.center_y - radius),
+            Point::new(self.data.center_x + radius, self.data.center_y + radius),
+        ))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let center = Point::new(self.data.center_x, self.data.center_y);
+        if self.data.fill_style != FillStyle::None
+            && point.distance_to(center) <= self.data.radius.abs()
+        {
+            return 0.0;
+        }
+        distance::point_to_circle(point, center, self.data.radius)
+    }
+
+    //A non uniform scale would need an ellipse, so the radius takes the average scale.
+    fn transform(&mut self, transform: &Transform) {
+        let center = transform.apply(Point::new(self.data.center_x, self.data.center_y));
+        self.data.center_x = center.x;
+        self.data.center_y = center.y;
+        self.data.radius *= transform.scale_factor();
+    }
+}
+
+impl Circle {
+    pub fn new(
+        center_x: f64,
+        center_y: f64,
+        radius: f64,
+        color: Color,
+        stroke_style: StrokeStyle,
+        fill_style: FillStyle,
+    ) -> Self {
+        let data = CircleData {
+            id: None,
+            center_x,
+            center_y,
+            radius,
+            color,
+            stroke_style,
+            fill_style,
+        };
+
+        Self { data }
+    }
+
+    //Returns None when the three points are on one line.
+    pub fn from_three_points(
+        first: (f64, f64),
+        second: (f64, f64),
+        third: (f64, f64),
+        color: Color,
+        stroke_style: StrokeStyle,
+        fill_style: FillStyle,
+    ) -> Option<Self> {
+        let (center_x, center_y) = circumcenter(first, second, third)?;
+        let radius = (first.0 - center_x).hypot(first.1 - center_y);
+        Some(Self::new(
+            center_x,
+            center_y,
+            radius,
+            color,
+            stroke_style,
+            fill_style,
+        ))
+    }
+
+    pub fn center_x(&self) -> f64 {
+        self.data.center_x
+    }
+
+    pub fn center_y(&self) -> f64 {
+        self.data.center_y
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.data.radius
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn fill_style(&self) -> &FillStyle {
+        &self.data.fill_style
+    }
+
+    pub fn set_fill_style(&mut self, fill_style: FillStyle) {
+        self.data.fill_style = fill_style;
+    }
+
+    pub fn set_center_x(&mut self, center_x: f64) {
+        self.data.center_x = center_x;
+    }
+
+    pub fn set_center_y(&mut self, center_y: f64) {
+        self.data.center_y = center_y;
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.data.radius = radius;
+    }
+}
+
+//Center of the circle passing through the three points.
+pub(crate) fn circumcenter(
+    first: (f64, f64),
+    second: (f64, f64),
+    third: (f64, f64),
+) -> Option<(f64, f64)> {
+    let (ax, ay) = first;
+    let (bx, by) = second;
+    let (cx, cy) = third;
+
+    let d = 2.0 * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+    if d.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let a = ax * ax + ay * ay;
+    let b = bx * bx + by * by;
+    let c = cx * cx + cy * cy;
+
+    let x = (a * (by - cy) + b * (cy - ay) + c * (ay - by)) / d;
+    let y = (a * (cx - bx) + b * (ax - cx) + c * (bx - ax)) / d;
+
+    Some((x, y))
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform, Vector},
+};
+
+use super::Leaf;
+
+//rotation is in radians, counterclockwise in figure coordinates.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EllipseData {
+    pub id: Option<FigureId>,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub radius_x: f64,
+    pub radius_y: f64,
+    pub rotation: f64,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+    pub fill_style: FillStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ellipse {
+    data: EllipseData,
+}
+
+impl Leaf for Ellipse {}
+
+impl Figure for Ellipse {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_ellipse(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Ellipse(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let (sin, cos) = self.data.rotation.sin_cos();
+        let (radius_x, radius_y) = (self.data.radius_x, self.data.radius_y);
+        let half_width = (radius_x * cos).hypot(radius_y * sin);
+        let half_height = (radius_x * sin).hypot(radius_y * cos);
+        Some(BoundingBox::new(
+            Point::new(
+                self.data.center_x - half_width,
+                self.data.center_y - half_height,
+            ),
+            Point::new(
+                self.data.center_x + half_width,
+                self.data.center_y + half_height,
+            ),
+        ))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let center = Point::new(self.data.center_x, self.data.center_y);
+        if self.data.fill_style != FillStyle::None {
+            let local = (point - center).rotate(-self.data.rotation);
+            let x = local.x / self.data.radius_x;
+            let y = local.y / self.data.radius_y;
+            if x * x + y * y <= 1.0 {
+                return 0.0;
+            }
+        }
+        distance::point_to_ellipse(
+            point,
+            center,
+            self.data.radius_x,
+            self.data.radius_y,
+            self.data.rotation,
+        )
+    }
+
+    //The axes are mapped and measured again. Exact unless the transform shears the ellipse.
+    fn transform(&mut self, transform: &Transform) {
+        let center = transform.apply(Point::new(self.data.center_x, self.data.center_y));
+        let axis_x =
+            transform.apply_vector(Vector::from_angle(self.data.rotation) * self.data.radius_x);
+        let axis_y = transform.apply_vector(
+            Vector::from_angle(self.data.rotation).perpendicular() * self.data.radius_y,
+        );
+        let Some(direction_x) = axis_x.normalize() else {
+            return;
+        };
+
+        self.data.center_x = center.x;
+        self.data.center_y = center.y;
+        self.data.rotation = direction_x.angle();
+        self.data.radius_x = axis_x.length();
+        self.data.radius_y = axis_y.dot(direction_x.perpendicular()).abs();
+    }
+}
+
+impl Ellipse {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        center_x: f64,
+        center_y: f64,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+        color: Color,
+        stroke_style: StrokeStyle,
+        fill_style: FillStyle,
+    ) -> Self {
+        let data = EllipseData {
+            id: None,
+            center_x,
+            center_y,
+            radius_x,
+            radius_y,
+            rotation,
+            color,
+            stroke_style,
+            fill_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn center_x(&self) -> f64 {
+        self.data.center_x
+    }
+
+    pub fn center_y(&self) -> f64 {
+        self.data.center_y
+    }
+
+    pub fn radius_x(&self) -> f64 {
+        self.data.radius_x
+    }
+
+    pub fn radius_y(&self) -> f64 {
+        self.data.radius_y
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.data.rotation
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn fill_style(&self) -> &FillStyle {
+        &self.data.fill_style
+    }
+
+    pub fn set_fill_style(&mut self, fill_style: FillStyle) {
+        self.data.fill_style = fill_style;
+    }
+
+    pub fn set_center_x(&mut self, center_x: f64) {
+        self.data.center_x = center_x;
+    }
+
+    pub fn set_center_y(&mut self, center_y: f64) {
+        self.data.center_y = center_y;
+    }
+
+    pub fn set_radius_x(&mut self, radius_x: f64) {
+        self.data.radius_x = radius_x;
+    }
+
+    pub fn set_radius_y(&mut self, radius_y: f64) {
+        self.data.radius_y = radius_y;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.data.rotation = rotation;
+    }
+
+    pub fn point_at(&self, angle: f64) -> (f64, f64) {
+        let (sin, cos) = self.data.rotation.sin_cos();
+        let x = self.data.radius_x * angle.cos();
+        let y = self.data.radius_y * angle.sin();
+        (
+            self.data.center_x + x * cos - y * sin,
+            self.data.center_y + x * sin + y * cos,
+        )
+    }
+}
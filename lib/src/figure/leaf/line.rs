@@ -1,19 +1,22 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::Color,
-    figure::{Figure, Visitor},
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
 };
 
 use super::Leaf;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LineData {
+    pub id: Option<FigureId>,
     pub start_x: f64,
     pub start_y: f64,
     pub end_x: f64,
     pub end_y: f64,
     pub color: Color,
+    pub stroke_style: StrokeStyle,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,16 +42,50 @@ impl Figure for Line {
     fn data(&self) -> crate::figure::FigureData {
         crate::figure::FigureData::Line(self.data.clone())
     }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points([self.start(), self.end()])
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        distance::point_to_segment(point, self.start(), self.end())
+    }
+
+    fn transform(&mut self, transform: &Transform) {
+        let start = transform.apply(self.start());
+        let end = transform.apply(self.end());
+        self.data.start_x = start.x;
+        self.data.start_y = start.y;
+        self.data.end_x = end.x;
+        self.data.end_y = end.y;
+    }
 }
 
 impl Line {
-    pub fn new(start_x: f64, start_y: f64, end_x: f64, end_y: f64, color: Color) -> Self {
+    pub fn new(
+        start_x: f64,
+        start_y: f64,
+        end_x: f64,
+        end_y: f64,
+        color: Color,
+        stroke_style: StrokeStyle,
+    ) -> Self {
         let data = LineData {
+            id: None,
             start_x,
             start_y,
             end_x,
             end_y,
             color,
+            stroke_style,
         };
 
         Self { data }
@@ -70,10 +107,26 @@ impl Line {
         self.data.end_y
     }
 
+    pub fn start(&self) -> Point {
+        Point::new(self.data.start_x, self.data.start_y)
+    }
+
+    pub fn end(&self) -> Point {
+        Point::new(self.data.end_x, self.data.end_y)
+    }
+
     pub fn color(&self) -> Color {
         self.data.color
     }
 
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
     pub fn set_start_x(&mut self, start_x: f64) {
         self.data.start_x = start_x;
     }
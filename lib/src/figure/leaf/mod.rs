@@ -2,7 +2,17 @@ use std::sync::Arc;
 
 use super::Figure;
 
+pub mod arc;
+pub mod bezier;
+pub mod block_ref;
+pub mod circle;
+pub mod ellipse;
 pub mod line;
+pub mod polyline;
+pub mod rectangle;
+pub mod spline;
+pub mod stroke;
+pub mod text;
 
 pub trait Leaf: Figure {}
 
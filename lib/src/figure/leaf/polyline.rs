@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
+};
+
+use super::Leaf;
+
+//A closed polyline connects its last vertex back to the first, so it doubles as a polygon.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PolylineData {
+    pub id: Option<FigureId>,
+    pub vertices: Vec<(f64, f64)>,
+    pub closed: bool,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+    pub fill_style: FillStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    data: PolylineData,
+}
+
+impl Leaf for Polyline {}
+
+impl Figure for Polyline {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_polyline(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Polyline(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.data.vertices.iter().copied())
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let vertices: Vec<Point> = self.data.vertices.iter().map(|v| (*v).into()).collect();
+        if self.data.closed
+            && self.data.fill_style != FillStyle::None
+            && distance::point_in_polygon(point, &vertices)
+        {
+            return 0.0;
+        }
+        distance::point_to_polyline(point, &vertices, self.data.closed)
+    }
+
+    fn transform(&mut self, transform: &Transform) {
+        for vertex in self.data.vertices.iter_mut() {
+            *vertex = transform.apply((*vertex).into()).into();
+        }
+    }
+}
+
+impl Polyline {
+    pub fn new(
+        vertices: Vec<(f64, f64)>,
+        closed: bool,
+        color: Color,
+        stroke_style: StrokeStyle,
+        fill_style: FillStyle,
+    ) -> Self {
+        let data = PolylineData {
+            id: None,
+            vertices,
+            closed,
+            color,
+            stroke_style,
+            fill_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn vertices(&self) -> &[(f64, f64)] {
+        &self.data.vertices
+    }
+
+    pub fn closed(&self) -> bool {
+        self.data.closed
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn fill_style(&self) -> &FillStyle {
+        &self.data.fill_style
+    }
+
+    pub fn set_fill_style(&mut self, fill_style: FillStyle) {
+        self.data.fill_style = fill_style;
+    }
+
+    pub fn push_vertex(&mut self, x: f64, y: f64) {
+        self.data.vertices.push((x, y));
+    }
+
+    pub fn set_vertex(&mut self, index: usize, x: f64, y: f64) {
+        if let Some(vertex) = self.data.vertices.get_mut(index) {
+            *vertex = (x, y);
+        }
+    }
+
+    pub fn set_closed(&mut self, closed: bool) {
+        self.data.closed = closed;
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, FillStyle, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform, Vector},
+};
+
+use super::Leaf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RectangleData {
+    pub id: Option<FigureId>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: f64,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+    pub fill_style: FillStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rectangle {
+    data: RectangleData,
+}
+
+impl Leaf for Rectangle {}
+
+impl Figure for Rectangle {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_rectangle(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Rectangle(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.corners())
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let corners = self.corners().map(Point::from);
+        if self.data.fill_style != FillStyle::None && distance::point_in_polygon(point, &corners) {
+            return 0.0;
+        }
+        distance::point_to_polyline(point, &corners, true)
+    }
+
+    //Exact for moves, rotations, mirrors and uniform scales.
+    //Shear can't be kept by a rectangle, so it is dropped.
+    fn transform(&mut self, transform: &Transform) {
+        let origin = transform.apply(Point::new(self.data.x, self.data.y));
+        let axis_x = transform.apply_vector(Vector::from_angle(self.data.rotation));
+        let axis_y = transform.apply_vector(Vector::from_angle(self.data.rotation).perpendicular());
+        let Some(direction_x) = axis_x.normalize() else {
+            return;
+        };
+
+        self.data.x = origin.x;
+        self.data.y = origin.y;
+        self.data.rotation = direction_x.angle();
+        self.data.width *= axis_x.length();
+        self.data.height *= axis_y.dot(direction_x.perpendicular());
+    }
+}
+
+impl Rectangle {
+    //(x, y) is the corner where drawing started.
+    //width and height can be negative when the opposite corner is to the left or below.
+    //rotation turns the rectangle around (x, y).
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        rotation: f64,
+        color: Color,
+        stroke_style: StrokeStyle,
+        fill_style: FillStyle,
+    ) -> Self {
+        let data = RectangleData {
+            id: None,
+            x,
+            y,
+            width,
+            height,
+            rotation,
+            color,
+            stroke_style,
+            fill_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.data.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.data.y
+    }
+
+    pub fn width(&self) -> f64 {
+        self.data.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.data.height
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.data.rotation
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn fill_style(&self) -> &FillStyle {
+        &self.data.fill_style
+    }
+
+    pub fn set_fill_style(&mut self, fill_style: FillStyle) {
+        self.data.fill_style = fill_style;
+    }
+
+    pub fn set_x(&mut self, x: f64) {
+        self.data.x = x;
+    }
+
+    pub fn set_y(&mut self, y: f64) {
+        self.data.y = y;
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        self.data.width = width;
+    }
+
+    pub fn set_height(&mut self, height: f64) {
+        self.data.height = height;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.data.rotation = rotation;
+    }
+
+    //Returns the four corners in drawing order, starting from (x, y).
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let origin = Point::new(self.data.x, self.data.y);
+        let side_x = Vector::from_angle(self.data.rotation) * self.data.width;
+        let side_y = Vector::from_angle(self.data.rotation).perpendicular() * self.data.height;
+        [
+            origin.into(),
+            (origin + side_x).into(),
+            (origin + side_x + side_y).into(),
+            (origin + side_y).into(),
+        ]
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{BoundingBox, Point, Transform},
+};
+
+use super::{
+    bezier::{chain_bounding_box, chain_distance, BezierSegment},
+    Leaf,
+};
+
+//A Catmull-Rom spline passing through every fit point.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SplineData {
+    pub id: Option<FigureId>,
+    pub fit_points: Vec<(f64, f64)>,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spline {
+    data: SplineData,
+}
+
+impl Leaf for Spline {}
+
+impl Figure for Spline {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_spline(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Spline(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let start = *self.data.fit_points.first()?;
+        Some(chain_bounding_box(start, &self.bezier_segments()))
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        match self.data.fit_points.first() {
+            Some(start) => chain_distance(point, *start, &self.bezier_segments()),
+            None => f64::INFINITY,
+        }
+    }
+
+    //The curve is built from the fit points by an affine invariant rule, so this is exact.
+    fn transform(&mut self, transform: &Transform) {
+        for point in self.data.fit_points.iter_mut() {
+            *point = transform.apply((*point).into()).into();
+        }
+    }
+}
+
+impl Spline {
+    pub fn new(fit_points: Vec<(f64, f64)>, color: Color, stroke_style: StrokeStyle) -> Self {
+        let data = SplineData {
+            id: None,
+            fit_points,
+            color,
+            stroke_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn fit_points(&self) -> &[(f64, f64)] {
+        &self.data.fit_points
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn push_fit_point(&mut self, x: f64, y: f64) {
+        self.data.fit_points.push((x, y));
+    }
+
+    pub fn set_fit_point(&mut self, index: usize, x: f64, y: f64) {
+        if let Some(point) = self.data.fit_points.get_mut(index) {
+            *point = (x, y);
+        }
+    }
+
+    //The same curve as cubic Bézier segments starting at the first fit point,
+    //so renderers only need to know how to draw cubic curves.
+    pub fn bezier_segments(&self) -> Vec<BezierSegment> {
+        let points = &self.data.fit_points;
+        let mut segments = Vec::new();
+        if points.len() < 2 {
+            return segments;
+        }
+
+        let last = points.len() - 1;
+        for i in 0..last {
+            let previous = points[i.saturating_sub(1)];
+            let start = points[i];
+            let end = points[i + 1];
+            let next = points[(i + 2).min(last)];
+
+            let control1 = (
+                start.0 + (end.0 - previous.0) / 6.0,
+                start.1 + (end.1 - previous.1) / 6.0,
+            );
+            let control2 = (
+                end.0 - (next.0 - start.0) / 6.0,
+                end.1 - (next.1 - start.1) / 6.0,
+            );
+            segments.push(BezierSegment::new(control1, control2, end));
+        }
+
+        segments
+    }
+}
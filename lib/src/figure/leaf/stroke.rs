@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform},
+};
+
+use super::Leaf;
+
+//A freehand ink stroke.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StrokeData {
+    pub id: Option<FigureId>,
+    pub points: Vec<(f64, f64)>,
+    pub color: Color,
+    pub stroke_style: StrokeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    data: StrokeData,
+}
+
+impl Leaf for Stroke {}
+
+impl Figure for Stroke {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_stroke(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Stroke(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.data.points.iter().copied())
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let points: Vec<Point> = self.data.points.iter().map(|p| (*p).into()).collect();
+        distance::point_to_polyline(point, &points, false)
+    }
+
+    fn transform(&mut self, transform: &Transform) {
+        for point in self.data.points.iter_mut() {
+            *point = transform.apply((*point).into()).into();
+        }
+    }
+}
+
+impl Stroke {
+    pub fn new(points: Vec<(f64, f64)>, color: Color, stroke_style: StrokeStyle) -> Self {
+        let data = StrokeData {
+            id: None,
+            points,
+            color,
+            stroke_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.data.points
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> &StrokeStyle {
+        &self.data.stroke_style
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: StrokeStyle) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn push_point(&mut self, x: f64, y: f64) {
+        self.data.points.push((x, y));
+    }
+
+    //Drops points that are closer than tolerance to the simplified outline (Ramer–Douglas–Peucker).
+    pub fn simplify(&mut self, tolerance: f64) {
+        let points = &self.data.points;
+        if points.len() < 3 {
+            return;
+        }
+
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+
+        //An explicit stack instead of recursion, long strokes can have thousands of points.
+        let mut ranges = vec![(0, points.len() - 1)];
+        while let Some((first, last)) = ranges.pop() {
+            let mut farthest = first;
+            let mut max_distance = 0.0;
+            for i in first + 1..last {
+                let distance = distance::point_to_segment(
+                    points[i].into(),
+                    points[first].into(),
+                    points[last].into(),
+                );
+                if distance > max_distance {
+                    max_distance = distance;
+                    farthest = i;
+                }
+            }
+
+            if max_distance > tolerance {
+                keep[farthest] = true;
+                ranges.push((first, farthest));
+                ranges.push((farthest, last));
+            }
+        }
+
+        self.data.points = points
+            .iter()
+            .zip(keep)
+            .filter_map(|(point, keep)| keep.then_some(*point))
+            .collect();
+    }
+
+    //Rounds off corners by Chaikin's corner cutting, keeping both end points in place.
+    pub fn smooth(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            let points = &self.data.points;
+            if points.len() < 3 {
+                return;
+            }
+
+            let mut smoothed = Vec::with_capacity(points.len() * 2);
+            smoothed.push(points[0]);
+            for pair in points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                smoothed.push((0.75 * a.0 + 0.25 * b.0, 0.75 * a.1 + 0.25 * b.1));
+                smoothed.push((0.25 * a.0 + 0.75 * b.0, 0.25 * a.1 + 0.75 * b.1));
+            }
+            smoothed.push(points[points.len() - 1]);
+
+            self.data.points = smoothed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(points: &[(f64, f64)]) -> Stroke {
+        Stroke::new(
+            points.to_vec(),
+            Color::new(0, 0, 0, 255),
+            StrokeStyle::default(),
+        )
+    }
+
+    const ZIGZAG: [(f64, f64); 5] = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (3.0, 1.0), (4.0, 0.0)];
+
+    #[test]
+    fn zero_tolerance_keeps_every_point() {
+        let mut zigzag = stroke(&ZIGZAG);
+        zigzag.simplify(0.0);
+        assert_eq!(zigzag.points(), ZIGZAG);
+    }
+
+    #[test]
+    fn simplify_drops_only_points_within_tolerance() {
+        let mut zigzag = stroke(&ZIGZAG);
+        zigzag.simplify(1.5);
+        assert_eq!(zigzag.points(), [(0.0, 0.0), (4.0, 0.0)]);
+
+        let mut zigzag = stroke(&ZIGZAG);
+        zigzag.simplify(0.5);
+        assert_eq!(zigzag.points(), ZIGZAG);
+    }
+
+    #[test]
+    fn collinear_points_collapse_to_the_end_points() {
+        let mut line = stroke(&[(0.0, 0.0), (1.0, 1.0), (2.5, 2.5), (3.0, 3.0), (5.0, 5.0)]);
+        line.simplify(0.01);
+        assert_eq!(line.points(), [(0.0, 0.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn short_strokes_are_left_alone() {
+        for points in [&[(1.0, 2.0)][..], &[(1.0, 2.0), (3.0, 4.0)]] {
+            let mut short = stroke(points);
+            short.simplify(10.0);
+            short.smooth(3);
+            assert_eq!(short.points(), points);
+        }
+    }
+
+    #[test]
+    fn smooth_keeps_the_end_points_and_adds_points() {
+        let mut zigzag = stroke(&ZIGZAG);
+        let mut count = ZIGZAG.len();
+        for _ in 0..3 {
+            zigzag.smooth(1);
+            let points = zigzag.points();
+            assert!(points.len() > count);
+            assert_eq!(points.first(), ZIGZAG.first());
+            assert_eq!(points.last(), ZIGZAG.last());
+            count = points.len();
+        }
+    }
+}
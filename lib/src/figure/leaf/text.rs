@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Color, StrokeStyle},
+    figure::{Figure, FigureId, Visitor},
+    geometry::{distance, BoundingBox, Point, Transform, Vector},
+};
+
+use super::Leaf;
+
+//Average glyph width relative to the font size, used for the approximate outline.
+const AVERAGE_GLYPH_WIDTH: f64 = 0.6;
+
+//Horizontal position of the text relative to its anchor point.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TextAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+//(x, y) is the anchor on the baseline. font_size is in figure units so text zooms with geometry.
+//rotation is in radians, counterclockwise in figure coordinates.
+//stroke_style outlines the glyphs when set, on top of the filled text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TextData {
+    pub id: Option<FigureId>,
+    pub x: f64,
+    pub y: f64,
+    pub text: String,
+    pub font_size: f64,
+    pub alignment: TextAlignment,
+    pub rotation: f64,
+    pub color: Color,
+    //Missing in text stored before outlines.
+    #[serde(default)]
+    pub stroke_style: Option<StrokeStyle>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    data: TextData,
+}
+
+impl Leaf for Text {}
+
+impl Figure for Text {
+    fn accept(&mut self, visitor: &dyn Visitor) {
+        visitor.visit_text(self);
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn data(&self) -> crate::figure::FigureData {
+        crate::figure::FigureData::Text(self.data.clone())
+    }
+
+    fn id(&self) -> Option<FigureId> {
+        self.data.id
+    }
+
+    fn set_id(&mut self, id: FigureId) {
+        self.data.id = Some(id);
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.outline())
+    }
+
+    fn distance_to(&self, point: Point) -> f64 {
+        let outline = self.outline();
+        if distance::point_in_polygon(point, &outline) {
+            return 0.0;
+        }
+        distance::point_to_polyline(point, &outline, true)
+    }
+
+    //Text stays readable, so a mirror only moves and turns it.
+    fn transform(&mut self, transform: &Transform) {
+        let anchor = transform.apply(Point::new(self.data.x, self.data.y));
+        let direction = transform.apply_vector(Vector::from_angle(self.data.rotation));
+
+        self.data.x = anchor.x;
+        self.data.y = anchor.y;
+        self.data.rotation = direction.angle();
+        self.data.font_size *= transform.scale_factor();
+    }
+}
+
+impl Text {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        x: f64,
+        y: f64,
+        text: String,
+        font_size: f64,
+        alignment: TextAlignment,
+        rotation: f64,
+        color: Color,
+        stroke_style: Option<StrokeStyle>,
+    ) -> Self {
+        let data = TextData {
+            id: None,
+            x,
+            y,
+            text,
+            font_size,
+            alignment,
+            rotation,
+            color,
+            stroke_style,
+        };
+
+        Self { data }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.data.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.data.y
+    }
+
+    pub fn text(&self) -> &str {
+        &self.data.text
+    }
+
+    pub fn font_size(&self) -> f64 {
+        self.data.font_size
+    }
+
+    pub fn alignment(&self) -> TextAlignment {
+        self.data.alignment
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.data.rotation
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.color
+    }
+
+    pub fn stroke_style(&self) -> Option<&StrokeStyle> {
+        self.data.stroke_style.as_ref()
+    }
+
+    pub fn set_stroke_style(&mut self, stroke_style: Option<StrokeStyle>) {
+        self.data.stroke_style = stroke_style;
+    }
+
+    pub fn set_x(&mut self, x: f64) {
+        self.data.x = x;
+    }
+
+    pub fn set_y(&mut self, y: f64) {
+        self.data.y = y;
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.data.text = text;
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.data.text.push(c);
+    }
+
+    pub fn pop_char(&mut self) -> Option<char> {
+        self.data.text.pop()
+    }
+
+    pub fn set_font_size(&mut self, font_size: f64) {
+        self.data.font_size = font_size;
+    }
+
+    pub fn set_alignment(&mut self, alignment: TextAlignment) {
+        self.data.alignment = alignment;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.data.rotation = rotation;
+    }
+
+    //Approximate box around the text, measured with an average glyph width since
+    //lib has no access to font metrics. Corners in counterclockwise order.
+    pub fn outline(&self) -> [Point; 4] {
+        let width =
+            self.data.text.chars().count() as f64 * self.data.font_size * AVERAGE_GLYPH_WIDTH;
+        let left = match self.data.alignment {
+            TextAlignment::Left => 0.0,
+            TextAlignment::Center => -width / 2.0,
+            TextAlignment::Right => -width,
+        };
+
+        let anchor = Point::new(self.data.x, self.data.y);
+        let along = Vector::from_angle(self.data.rotation);
+        let up = along.perpendicular();
+        let bottom_left = anchor + along * left;
+        let bottom_right = bottom_left + along * width;
+        let top = up * self.data.font_size;
+        [
+            bottom_left,
+            bottom_right,
+            bottom_right + top,
+            bottom_left + top,
+        ]
+    }
+}
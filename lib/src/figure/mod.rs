@@ -2,14 +2,46 @@ use std::any::Any;
 
 use as_dyn_trait::as_dyn_trait;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use self::leaf::line::{Line, LineData};
+use crate::geometry::{BoundingBox, Point, Transform};
 
+use self::{
+    composite::group::{Group, GroupData},
+    leaf::{
+        arc::{Arc, ArcData},
+        bezier::{Bezier, BezierData},
+        block_ref::{BlockRef, BlockRefData},
+        circle::{Circle, CircleData},
+        ellipse::{Ellipse, EllipseData},
+        line::{Line, LineData},
+        polyline::{Polyline, PolylineData},
+        rectangle::{Rectangle, RectangleData},
+        spline::{Spline, SplineData},
+        stroke::{Stroke, StrokeData},
+        text::{Text, TextData},
+    },
+};
+
+pub mod block;
 pub mod composite;
 pub mod leaf;
 
+//Assigned by the server when a figure is added to a room. None until then.
+pub type FigureId = Uuid;
+
 pub trait Visitor {
     fn visit_line(&self, figure: &mut Line);
+    fn visit_rectangle(&self, figure: &mut Rectangle);
+    fn visit_circle(&self, figure: &mut Circle);
+    fn visit_arc(&self, figure: &mut Arc);
+    fn visit_ellipse(&self, figure: &mut Ellipse);
+    fn visit_polyline(&self, figure: &mut Polyline);
+    fn visit_bezier(&self, figure: &mut Bezier);
+    fn visit_spline(&self, figure: &mut Spline);
+    fn visit_text(&self, figure: &mut Text);
+    fn visit_stroke(&self, figure: &mut Stroke);
+    fn visit_block_ref(&self, figure: &mut BlockRef);
 }
 
 #[as_dyn_trait]
@@ -18,19 +50,111 @@ pub trait Figure {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn data(&self) -> FigureData;
+    fn id(&self) -> Option<FigureId>;
+    fn set_id(&mut self, id: FigureId);
+    //None when the figure has nothing to bound, like an empty group.
+    fn bounding_box(&self) -> Option<BoundingBox>;
+    fn distance_to(&self, point: Point) -> f64;
+    fn transform(&mut self, transform: &Transform);
 }
 
 impl From<FigureData> for Box<dyn Figure> {
     fn from(val: FigureData) -> Self {
-        let figure: Box<dyn Figure> = match val {
+        let id = val.id();
+        let mut figure: Box<dyn Figure> = match val {
             FigureData::Line(data) => Box::new(Line::new(
                 data.start_x,
                 data.start_y,
                 data.end_x,
                 data.end_y,
                 data.color,
+                data.stroke_style,
+            )),
+            FigureData::Rectangle(data) => Box::new(Rectangle::new(
+                data.x,
+                data.y,
+                data.width,
+                data.height,
+                data.rotation,
+                data.color,
+                data.stroke_style,
+                data.fill_style,
+            )),
+            FigureData::Circle(data) => Box::new(Circle::new(
+                data.center_x,
+                data.center_y,
+                data.radius,
+                data.color,
+                data.stroke_style,
+                data.fill_style,
+            )),
+            FigureData::Arc(data) => Box::new(Arc::new(
+                data.center_x,
+                data.center_y,
+                data.radius,
+                data.start_angle,
+                data.end_angle,
+                data.direction,
+                data.color,
+                data.stroke_style,
+            )),
+            FigureData::Ellipse(data) => Box::new(Ellipse::new(
+                data.center_x,
+                data.center_y,
+                data.radius_x,
+                data.radius_y,
+                data.rotation,
+                data.color,
+                data.stroke_style,
+                data.fill_style,
+            )),
+            FigureData::Polyline(data) => Box::new(Polyline::new(
+                data.vertices,
+                data.closed,
+                data.color,
+                data.stroke_style,
+                data.fill_style,
+            )),
+            FigureData::Bezier(data) => Box::new(Bezier::new(
+                data.start,
+                data.segments,
+                data.color,
+                data.stroke_style,
+            )),
+            FigureData::Spline(data) => {
+                Box::new(Spline::new(data.fit_points, data.color, data.stroke_style))
+            }
+            FigureData::Text(data) => Box::new(Text::new(
+                data.x,
+                data.y,
+                data.text,
+                data.font_size,
+                data.alignment,
+                data.rotation,
+                data.color,
+                data.stroke_style,
+            )),
+            FigureData::Stroke(data) => {
+                Box::new(Stroke::new(data.points, data.color, data.stroke_style))
+            }
+            FigureData::BlockRef(data) => Box::new(BlockRef::new(
+                data.name,
+                data.insertion_x,
+                data.insertion_y,
+                data.scale_x,
+                data.scale_y,
+                data.rotation,
+            )),
+            FigureData::Group(data) => Box::new(Group::new(
+                data.children
+                    .into_iter()
+                    .map(|child| child.into())
+                    .collect(),
             )),
         };
+        if let Some(id) = id {
+            figure.set_id(id);
+        }
         figure
     }
 }
@@ -38,4 +162,52 @@ impl From<FigureData> for Box<dyn Figure> {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum FigureData {
     Line(LineData),
+    Rectangle(RectangleData),
+    Circle(CircleData),
+    Arc(ArcData),
+    Ellipse(EllipseData),
+    Polyline(PolylineData),
+    Bezier(BezierData),
+    Spline(SplineData),
+    Text(TextData),
+    Stroke(StrokeData),
+    BlockRef(BlockRefData),
+    Group(GroupData),
+}
+
+impl FigureData {
+    pub fn id(&self) -> Option<FigureId> {
+        match self {
+            FigureData::Line(data) => data.id,
+            FigureData::Rectangle(data) => data.id,
+            FigureData::Circle(data) => data.id,
+            FigureData::Arc(data) => data.id,
+            FigureData::Ellipse(data) => data.id,
+            FigureData::Polyline(data) => data.id,
+            FigureData::Bezier(data) => data.id,
+            FigureData::Spline(data) => data.id,
+            FigureData::Text(data) => data.id,
+            FigureData::Stroke(data) => data.id,
+            FigureData::BlockRef(data) => data.id,
+            FigureData::Group(data) => data.id,
+        }
+    }
+
+    pub fn set_id(&mut self, id: FigureId) {
+        let target = match self {
+            FigureData::Line(data) => &mut data.id,
+            FigureData::Rectangle(data) => &mut data.id,
+            FigureData::Circle(data) => &mut data.id,
+            FigureData::Arc(data) => &mut data.id,
+            FigureData::Ellipse(data) => &mut data.id,
+            FigureData::Polyline(data) => &mut data.id,
+            FigureData::Bezier(data) => &mut data.id,
+            FigureData::Spline(data) => &mut data.id,
+            FigureData::Text(data) => &mut data.id,
+            FigureData::Stroke(data) => &mut data.id,
+            FigureData::BlockRef(data) => &mut data.id,
+            FigureData::Group(data) => &mut data.id,
+        };
+        *target = Some(id);
+    }
 }
@@ -0,0 +1,207 @@
+use super::{point::Point, transform::Transform};
+
+//Axis aligned box in figure coordinates. min is never greater than max.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    //The corners can be given in any order.
+    pub fn new(first: Point, second: Point) -> Self {
+        Self {
+            min: Point::new(first.x.min(second.x), first.y.min(second.y)),
+            max: Point::new(first.x.max(second.x), first.y.max(second.y)),
+        }
+    }
+
+    pub fn from_point(point: Point) -> Self {
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    //Returns None when there are no points.
+    pub fn from_points<I, P>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Point>,
+    {
+        let mut points = points.into_iter().map(Into::into);
+        let first = points.next()?;
+        Some(points.fold(Self::from_point(first), |bounding_box, point| {
+            bounding_box.include(point)
+        }))
+    }
+
+    //Tight box around a cubic Bézier curve, using the points where the curve turns.
+    pub fn from_cubic_bezier(start: Point, control1: Point, control2: Point, end: Point) -> Self {
+        let mut bounding_box = Self::new(start, end);
+
+        let axes: [fn(Point) -> f64; 2] = [|point| point.x, |point| point.y];
+        for axis in axes {
+            let (p0, p1, p2, p3) = (axis(start), axis(control1), axis(control2), axis(end));
+            //Derivative coefficients of the curve along this axis, as a * t^2 + b * t + c.
+            let a = 3.0 * (-p0 + 3.0 * p1 - 3.0 * p2 + p3);
+            let b = 6.0 * (p0 - 2.0 * p1 + p2);
+            let c = 3.0 * (p1 - p0);
+            for t in quadratic_roots(a, b, c) {
+                if t > 0.0 && t < 1.0 {
+                    bounding_box =
+                        bounding_box.include(cubic_bezier_point(start, control1, control2, end, t));
+                }
+            }
+        }
+
+        bounding_box
+    }
+
+    pub fn include(&self, point: Point) -> Self {
+        Self {
+            min: Point::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            max: Point::new(self.max.x.max(point.x), self.max.y.max(point.y)),
+        }
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> Self {
+        self.include(other.min).include(other.max)
+    }
+
+    //Grows the box by margin on every side.
+    pub fn expand(&self, margin: f64) -> Self {
+        Self {
+            min: Point::new(self.min.x - margin, self.min.y - margin),
+            max: Point::new(self.max.x + margin, self.max.y + margin),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Point {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            self.min,
+            Point::new(self.max.x, self.min.y),
+            self.max,
+            Point::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains(other.min) && self.contains(other.max)
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    //Box around the transformed corners, so it may be larger than the transformed shape.
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        let corners = self.corners().map(|corner| transform.apply(corner));
+        Self::from_points(corners).unwrap_or(*self)
+    }
+}
+
+pub(crate) fn cubic_bezier_point(
+    start: Point,
+    control1: Point,
+    control2: Point,
+    end: Point,
+    t: f64,
+) -> Point {
+    let s = 1.0 - t;
+    let (w0, w1, w2, w3) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+    Point::new(
+        w0 * start.x + w1 * control1.x + w2 * control2.x + w3 * end.x,
+        w0 * start.y + w1 * control1.y + w2 * control2.y + w3 * end.y,
+    )
+}
+
+//Real roots of a * t^2 + b * t + c, falling back to the linear case when a is zero.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if super::approx_zero(a) {
+        if super::approx_zero(b) {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+    let root = discriminant.sqrt();
+    vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cubic_bezier_bulging_past_its_end_points() {
+        //Peaks at y = 7.5 halfway, above both end points.
+        let bounding_box = BoundingBox::from_cubic_bezier(
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 0.0),
+        );
+        assert!(bounding_box.min.approx_eq(Point::new(0.0, 0.0)));
+        assert!(bounding_box.max.approx_eq(Point::new(10.0, 7.5)));
+    }
+
+    #[test]
+    fn cubic_bezier_matches_sampling() {
+        //Turns on both axes, checked against dense sampling.
+        let (start, control1, control2, end) = (
+            Point::new(0.0, 0.0),
+            Point::new(20.0, 5.0),
+            Point::new(-10.0, 5.0),
+            Point::new(10.0, 0.0),
+        );
+        let bounding_box = BoundingBox::from_cubic_bezier(start, control1, control2, end);
+        let sampled = BoundingBox::from_points(
+            (0..=10_000)
+                .map(|i| cubic_bezier_point(start, control1, control2, end, i as f64 / 10_000.0)),
+        )
+        .unwrap();
+        assert!(bounding_box.min.approx_eq_with(sampled.min, 1e-6));
+        assert!(bounding_box.max.approx_eq_with(sampled.max, 1e-6));
+    }
+
+    #[test]
+    fn straight_cubic_bezier() {
+        let bounding_box = BoundingBox::from_cubic_bezier(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            Point::new(3.0, 3.0),
+        );
+        assert_eq!(
+            bounding_box,
+            BoundingBox::new(Point::new(0.0, 0.0), Point::new(3.0, 3.0))
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::f64::consts::TAU;
+
+use super::{
+    approx_zero,
+    bounding_box::{cubic_bezier_point, BoundingBox},
+    point::{Point, Vector},
+};
+
+//Samples per cubic Bézier segment when measuring distance to it.
+const BEZIER_DISTANCE_STEPS: usize = 32;
+
+//Iterations for the ellipse distance, which converges to well below drawing precision.
+const ELLIPSE_DISTANCE_ITERATIONS: usize = 4;
+
+pub fn closest_point_on_segment(point: Point, start: Point, end: Point) -> Point {
+    let direction = end - start;
+    let length_squared = direction.length_squared();
+    if approx_zero(length_squared) {
+        return start;
+    }
+
+    let t = ((point - start).dot(direction) / length_squared).clamp(0.0, 1.0);
+    start + direction * t
+}
+
+pub fn point_to_segment(point: Point, start: Point, end: Point) -> f64 {
+    point.distance_to(closest_point_on_segment(point, start, end))
+}
+
+//A closed polyline also measures the edge from the last vertex back to the first.
+pub fn point_to_polyline(point: Point, vertices: &[Point], closed: bool) -> f64 {
+    match vertices {
+        [] => f64::INFINITY,
+        [only] => point.distance_to(*only),
+        _ => {
+            let mut distance = vertices
+                .windows(2)
+                .map(|edge| point_to_segment(point, edge[0], edge[1]))
+                .fold(f64::INFINITY, f64::min);
+            if closed {
+                let last = vertices[vertices.len() - 1];
+                distance = distance.min(point_to_segment(point, last, vertices[0]));
+            }
+            distance
+        }
+    }
+}
+
+//Even-odd test, so self intersecting outlines behave like the canvas fill.
+pub fn point_in_polygon(point: Point, vertices: &[Point]) -> bool {
+    let mut inside = false;
+    let mut previous = match vertices.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for current in vertices {
+        if (current.y > point.y) != (previous.y > point.y) {
+            let x = current.x
+                + (point.y - current.y) * (previous.x - current.x) / (previous.y - current.y);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+        previous = *current;
+    }
+    inside
+}
+
+pub fn point_to_circle(point: Point, center: Point, radius: f64) -> f64 {
+    (point.distance_to(center) - radius.abs()).abs()
+}
+
+//sweep is signed, positive going counterclockwise from start_angle.
+pub fn angle_in_sweep(angle: f64, start_angle: f64, sweep: f64) -> bool {
+    if sweep >= 0.0 {
+        (angle - start_angle).rem_euclid(TAU) <= sweep
+    } else {
+        (start_angle - angle).rem_euclid(TAU) <= -sweep
+    }
+}
+
+//sweep is signed, positive going counterclockwise from start_angle.
+pub fn point_to_arc(point: Point, center: Point, radius: f64, start_angle: f64, sweep: f64) -> f64 {
+    let angle = (point - center).angle();
+    if angle_in_sweep(angle, start_angle, sweep) {
+        return point_to_circle(point, center, radius);
+    }
+
+    let start = center + Vector::from_angle(start_angle) * radius;
+    let end = center + Vector::from_angle(start_angle + sweep) * radius;
+    point.distance_to(start).min(point.distance_to(end))
+}
+
+//rotation is in radians, counterclockwise.
+pub fn point_to_ellipse(
+    point: Point,
+    center: Point,
+    radius_x: f64,
+    radius_y: f64,
+    rotation: f64,
+) -> f64 {
+    let local = (point - center).rotate(-rotation);
+    let (a, b) = (radius_x.abs(), radius_y.abs());
+
+    //A flat ellipse is just a segment.
+    if approx_zero(a) || approx_zero(b) {
+        let half = Vector::new(a, b);
+        return point_to_segment(
+            Point::new(local.x, local.y),
+            Point::new(0.0, 0.0) - half,
+            Point::new(0.0, 0.0) + half,
+        );
+    }
+
+    //By symmetry only the first quadrant is needed. Each step moves the guess along
+    //the circle of curvature at the current point, which converges in a few steps.
+    let (px, py) = (local.x.abs(), local.y.abs());
+    let (mut tx, mut ty) = (
+        std::f64::consts::FRAC_1_SQRT_2,
+        std::f64::consts::FRAC_1_SQRT_2,
+    );
+    for _ in 0..ELLIPSE_DISTANCE_ITERATIONS {
+        let x = a * tx;
+        let y = b * ty;
+        let ex = (a * a - b * b) * tx.powi(3) / a;
+        let ey = (b * b - a * a) * ty.powi(3) / b;
+        let r = (x - ex).hypot(y - ey);
+        let q = (px - ex).hypot(py - ey);
+        if approx_zero(q) {
+            break;
+        }
+        tx = ((px - ex) * r / q + ex) / a;
+        ty = ((py - ey) * r / q + ey) / b;
+        tx = tx.clamp(0.0, 1.0);
+        ty = ty.clamp(0.0, 1.0);
+        let t = tx.hypot(ty);
+        tx /= t;
+        ty /= t;
+    }
+
+    (px - a * tx).hypot(py - b * ty)
+}
+
+pub fn point_to_cubic_bezier(
+    point: Point,
+    start: Point,
+    control1: Point,
+    control2: Point,
+    end: Point,
+) -> f64 {
+    let samples: Vec<Point> = (0..=BEZIER_DISTANCE_STEPS)
+        .map(|i| {
+            cubic_bezier_point(
+                start,
+                control1,
+                control2,
+                end,
+                i as f64 / BEZIER_DISTANCE_STEPS as f64,
+            )
+        })
+        .collect();
+    point_to_polyline(point, &samples, false)
+}
+
+//Whether a shape, given by its distance function, comes within tolerance of the area.
+//A distance changes no faster than the point it is measured from, so a part of the area
+//farther from the shape than its half diagonal cannot touch it. The other parts are
+//quartered until they are smaller than the tolerance.
+pub fn shape_touches_area(
+    distance: &impl Fn(Point) -> f64,
+    area: &BoundingBox,
+    tolerance: f64,
+) -> bool {
+    let center = area.center();
+    let center_distance = distance(center);
+    let half_diagonal = area.min.distance_to(area.max) / 2.0;
+    if center_distance > half_diagonal + tolerance {
+        return false;
+    }
+    if center_distance <= tolerance || half_diagonal <= tolerance {
+        return true;
+    }
+
+    [
+        BoundingBox::new(area.min, center),
+        BoundingBox::new(center, area.max),
+        BoundingBox::new(
+            Point::new(area.min.x, center.y),
+            Point::new(center.x, area.max.y),
+        ),
+        BoundingBox::new(
+            Point::new(center.x, area.min.y),
+            Point::new(area.max.x, center.y),
+        ),
+    ]
+    .iter()
+    .any(|quarter| shape_touches_area(distance, quarter, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::*;
+    use crate::geometry::approx_eq_with;
+
+    const PRECISION: f64 = 1e-6;
+
+    #[test]
+    fn point_to_ellipse_on_the_axes() {
+        let center = Point::new(0.0, 0.0);
+        assert!(approx_eq_with(
+            point_to_ellipse(Point::new(5.0, 0.0), center, 2.0, 1.0, 0.0),
+            3.0,
+            PRECISION
+        ));
+        assert!(approx_eq_with(
+            point_to_ellipse(Point::new(0.0, -4.0), center, 2.0, 1.0, 0.0),
+            3.0,
+            PRECISION
+        ));
+        //Inside, the nearest points are the ends of the minor axis.
+        assert!(approx_eq_with(
+            point_to_ellipse(center, center, 2.0, 1.0, 0.0),
+            1.0,
+            PRECISION
+        ));
+    }
+
+    #[test]
+    fn point_to_ellipse_off_the_axes() {
+        //An ellipse with equal radii is a circle.
+        let distance = point_to_ellipse(Point::new(4.0, 5.0), Point::new(1.0, 1.0), 2.0, 2.0, 0.3);
+        assert!(approx_eq_with(distance, 3.0, PRECISION));
+
+        //(3, 4) is on the ellipse with radii 3 * sqrt(2) and 4 * sqrt(2), so the
+        //point twice as far out along the normal there is one normal length away.
+        let (a, b) = (3.0 * 2f64.sqrt(), 4.0 * 2f64.sqrt());
+        let normal = Vector::new(3.0 / (a * a), 4.0 / (b * b));
+        let outside = Point::new(3.0, 4.0) + normal * (2.0 / normal.length());
+        let distance = point_to_ellipse(outside, Point::new(0.0, 0.0), a, b, 0.0);
+        assert!(approx_eq_with(distance, 2.0, PRECISION));
+    }
+
+    #[test]
+    fn point_to_ellipse_rotated() {
+        let distance = point_to_ellipse(
+            Point::new(10.0, 15.0),
+            Point::new(10.0, 10.0),
+            2.0,
+            1.0,
+            FRAC_PI_2,
+        );
+        assert!(approx_eq_with(distance, 3.0, PRECISION));
+    }
+
+    #[test]
+    fn angle_in_negative_sweep() {
+        //Clockwise a quarter turn from 0 to -pi / 2.
+        assert!(angle_in_sweep(-FRAC_PI_4, 0.0, -FRAC_PI_2));
+        assert!(angle_in_sweep(3.0 * FRAC_PI_2, 0.0, -FRAC_PI_2));
+        assert!(angle_in_sweep(0.0, 0.0, -FRAC_PI_2));
+        assert!(!angle_in_sweep(FRAC_PI_4, 0.0, -FRAC_PI_2));
+        assert!(!angle_in_sweep(PI, 0.0, -FRAC_PI_2));
+
+        //Crossing the x axis, from pi / 4 down to -pi / 4.
+        assert!(angle_in_sweep(0.0, FRAC_PI_4, -FRAC_PI_2));
+        assert!(angle_in_sweep(2.0 * PI, FRAC_PI_4, -FRAC_PI_2));
+        assert!(!angle_in_sweep(FRAC_PI_2, FRAC_PI_4, -FRAC_PI_2));
+    }
+
+    #[test]
+    fn point_to_segment_clamps_to_the_ends() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(4.0, 0.0));
+        assert_eq!(point_to_segment(Point::new(2.0, 3.0), start, end), 3.0);
+        assert_eq!(point_to_segment(Point::new(7.0, 4.0), start, end), 5.0);
+        assert_eq!(
+            point_to_segment(Point::new(1.0, 1.0), start, start),
+            2f64.sqrt()
+        );
+    }
+
+    #[test]
+    fn diagonal_segment_touches_only_areas_it_passes() {
+        let distance =
+            |point| point_to_segment(point, Point::new(0.0, 0.0), Point::new(10.0, 10.0));
+        let area = |x1, y1, x2, y2| BoundingBox::new(Point::new(x1, y1), Point::new(x2, y2));
+
+        //Inside the segment's bounding box, but below the diagonal.
+        assert!(!shape_touches_area(
+            &distance,
+            &area(6.0, 0.0, 10.0, 4.0),
+            0.01
+        ));
+        assert!(shape_touches_area(
+            &distance,
+            &area(4.0, 0.0, 6.0, 10.0),
+            0.01
+        ));
+        //The corner of the area just reaches the segment.
+        assert!(shape_touches_area(
+            &distance,
+            &area(5.0, 0.0, 9.0, 5.0),
+            0.01
+        ));
+        assert!(!shape_touches_area(
+            &distance,
+            &area(5.1, 0.0, 9.0, 5.0),
+            0.01
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use super::{approx_zero, point::Point, TOLERANCE};
+
+//Returns None for parallel segments, including overlapping ones.
+pub fn segment_segment(
+    first_start: Point,
+    first_end: Point,
+    second_start: Point,
+    second_end: Point,
+) -> Option<Point> {
+    let first = first_end - first_start;
+    let second = second_end - second_start;
+    let denominator = first.cross(second);
+    if approx_zero(denominator) {
+        return None;
+    }
+
+    let offset = second_start - first_start;
+    let t = offset.cross(second) / denominator;
+    let u = offset.cross(first) / denominator;
+    if in_unit_range(t) && in_unit_range(u) {
+        Some(first_start + first * t)
+    } else {
+        None
+    }
+}
+
+//Zero, one (tangent) or two points, ordered from start to end.
+pub fn segment_circle(start: Point, end: Point, center: Point, radius: f64) -> Vec<Point> {
+    let direction = end - start;
+    let a = direction.length_squared();
+    if approx_zero(a) {
+        return Vec::new();
+    }
+
+    let offset = start - center;
+    let b = 2.0 * offset.dot(direction);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < -TOLERANCE {
+        return Vec::new();
+    }
+
+    let root = discriminant.max(0.0).sqrt();
+    let mut parameters = vec![(-b - root) / (2.0 * a)];
+    if !approx_zero(root) {
+        parameters.push((-b + root) / (2.0 * a));
+    }
+
+    parameters
+        .into_iter()
+        .filter(|t| in_unit_range(*t))
+        .map(|t| start + direction * t)
+        .collect()
+}
+
+fn in_unit_range(t: f64) -> bool {
+    (-TOLERANCE..=1.0 + TOLERANCE).contains(&t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Point {
+        Point::new(x, y)
+    }
+
+    #[test]
+    fn crossing_segments() {
+        let crossing = segment_segment(
+            point(0.0, 0.0),
+            point(4.0, 4.0),
+            point(0.0, 4.0),
+            point(4.0, 0.0),
+        );
+        assert!(crossing.unwrap().approx_eq(point(2.0, 2.0)));
+        assert!(segment_segment(
+            point(0.0, 0.0),
+            point(1.0, 1.0),
+            point(0.0, 4.0),
+            point(4.0, 0.0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn parallel_segments() {
+        assert!(segment_segment(
+            point(0.0, 0.0),
+            point(4.0, 0.0),
+            point(0.0, 1.0),
+            point(4.0, 1.0)
+        )
+        .is_none());
+        //Overlapping counts as parallel too.
+        assert!(segment_segment(
+            point(0.0, 0.0),
+            point(4.0, 0.0),
+            point(2.0, 0.0),
+            point(6.0, 0.0)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn touching_segments() {
+        //End to end.
+        let touching = segment_segment(
+            point(0.0, 0.0),
+            point(2.0, 2.0),
+            point(2.0, 2.0),
+            point(4.0, 0.0),
+        );
+        assert!(touching.unwrap().approx_eq(point(2.0, 2.0)));
+        //An end on the middle of the other.
+        let touching = segment_segment(
+            point(0.0, 0.0),
+            point(4.0, 0.0),
+            point(2.0, 0.0),
+            point(2.0, 3.0),
+        );
+        assert!(touching.unwrap().approx_eq(point(2.0, 0.0)));
+    }
+
+    #[test]
+    fn tangent_segment_circle() {
+        let tangent = segment_circle(point(-5.0, 1.0), point(5.0, 1.0), point(0.0, 0.0), 1.0);
+        assert_eq!(tangent.len(), 1);
+        assert!(tangent[0].approx_eq(point(0.0, 1.0)));
+    }
+
+    #[test]
+    fn secant_and_missing_segment_circle() {
+        let secant = segment_circle(point(-5.0, 0.0), point(5.0, 0.0), point(0.0, 0.0), 2.0);
+        assert_eq!(secant.len(), 2);
+        assert!(secant[0].approx_eq(point(-2.0, 0.0)));
+        assert!(secant[1].approx_eq(point(2.0, 0.0)));
+
+        //Inside the circle the segment never reaches it.
+        assert!(segment_circle(point(-1.0, 0.0), point(1.0, 0.0), point(0.0, 0.0), 2.0).is_empty());
+        assert!(segment_circle(point(-5.0, 3.0), point(5.0, 3.0), point(0.0, 0.0), 2.0).is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+pub use self::{
+    bounding_box::BoundingBox,
+    point::{Point, Vector},
+    transform::Transform,
+};
+
+pub mod bounding_box;
+pub mod distance;
+pub mod intersection;
+pub mod point;
+pub mod transform;
+
+//Lengths and parameters closer than this are treated as equal.
+pub const TOLERANCE: f64 = 1e-9;
+
+pub fn approx_eq(a: f64, b: f64) -> bool {
+    approx_eq_with(a, b, TOLERANCE)
+}
+
+pub fn approx_eq_with(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+pub fn approx_zero(value: f64) -> bool {
+    value.abs() <= TOLERANCE
+}
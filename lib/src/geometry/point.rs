@@ -0,0 +1,187 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use super::{approx_eq_with, approx_zero, TOLERANCE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance_to(&self, other: Point) -> f64 {
+        (other - *self).length()
+    }
+
+    //Point at t along the way to other, t = 0 is self and t = 1 is other.
+    pub fn lerp(&self, other: Point, t: f64) -> Point {
+        *self + (other - *self) * t
+    }
+
+    pub fn midpoint(&self, other: Point) -> Point {
+        self.lerp(other, 0.5)
+    }
+
+    pub fn approx_eq(&self, other: Point) -> bool {
+        self.approx_eq_with(other, TOLERANCE)
+    }
+
+    pub fn approx_eq_with(&self, other: Point, tolerance: f64) -> bool {
+        approx_eq_with(self.x, other.x, tolerance) && approx_eq_with(self.y, other.y, tolerance)
+    }
+}
+
+impl Vector {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    //Unit vector at angle radians counterclockwise from the x axis.
+    pub fn from_angle(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self { x: cos, y: sin }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    pub fn dot(&self, other: Vector) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    //Positive when other is counterclockwise from self.
+    pub fn cross(&self, other: Vector) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    //Returns None for a zero length vector.
+    pub fn normalize(&self) -> Option<Vector> {
+        let length = self.length();
+        if approx_zero(length) {
+            None
+        } else {
+            Some(*self / length)
+        }
+    }
+
+    //The vector turned a quarter turn counterclockwise.
+    pub fn perpendicular(&self) -> Vector {
+        Vector::new(-self.y, self.x)
+    }
+
+    pub fn rotate(&self, angle: f64) -> Vector {
+        let (sin, cos) = angle.sin_cos();
+        Vector::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    pub fn approx_zero(&self) -> bool {
+        approx_zero(self.length())
+    }
+}
+
+impl From<(f64, f64)> for Point {
+    fn from((x, y): (f64, f64)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl From<Point> for (f64, f64) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl Sub<Point> for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Point) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, vector: Vector) -> Point {
+        Point::new(self.x + vector.x, self.y + vector.y)
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Point;
+
+    fn sub(self, vector: Vector) -> Point {
+        Point::new(self.x - vector.x, self.y - vector.y)
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, vector: Vector) {
+        *self = *self + vector;
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, vector: Vector) {
+        *self = *self - vector;
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vector {
+    type Output = Vector;
+
+    fn mul(self, scalar: f64) -> Vector {
+        Vector::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl Div<f64> for Vector {
+    type Output = Vector;
+
+    fn div(self, scalar: f64) -> Vector {
+        Vector::new(self.x / scalar, self.y / scalar)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y)
+    }
+}
@@ -0,0 +1,222 @@
+use super::{
+    approx_zero,
+    point::{Point, Vector},
+};
+
+//2D affine transform mapping (x, y) to (a * x + c * y + e, b * x + d * y + f),
+//the same layout the canvas transform uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn translation(dx: f64, dy: f64) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, dx, dy)
+    }
+
+    //Counterclockwise around the origin.
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    pub fn rotation_about(center: Point, angle: f64) -> Self {
+        Self::about(center, Self::rotation(angle))
+    }
+
+    pub fn scale(scale_x: f64, scale_y: f64) -> Self {
+        Self::new(scale_x, 0.0, 0.0, scale_y, 0.0, 0.0)
+    }
+
+    pub fn scale_about(center: Point, scale_x: f64, scale_y: f64) -> Self {
+        Self::about(center, Self::scale(scale_x, scale_y))
+    }
+
+    //Reflection across the line through first and second.
+    //Returns the identity when the two points are the same.
+    pub fn mirror(first: Point, second: Point) -> Self {
+        let Some(direction) = (second - first).normalize() else {
+            return Self::identity();
+        };
+        let angle = direction.angle() * 2.0;
+        let (sin, cos) = angle.sin_cos();
+        Self::about(first, Self::new(cos, sin, sin, -cos, 0.0, 0.0))
+    }
+
+    //Applies transform with center as its origin.
+    fn about(center: Point, transform: Transform) -> Self {
+        Self::translation(-center.x, -center.y)
+            .then(&transform)
+            .then(&Self::translation(center.x, center.y))
+    }
+
+    //self applied first, then next.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform::new(
+            next.a * self.a + next.c * self.b,
+            next.b * self.a + next.d * self.b,
+            next.a * self.c + next.c * self.d,
+            next.b * self.c + next.d * self.d,
+            next.a * self.e + next.c * self.f + next.e,
+            next.b * self.e + next.d * self.f + next.f,
+        )
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(
+            self.a * point.x + self.c * point.y + self.e,
+            self.b * point.x + self.d * point.y + self.f,
+        )
+    }
+
+    //Vectors ignore the translation part.
+    pub fn apply_vector(&self, vector: Vector) -> Vector {
+        Vector::new(
+            self.a * vector.x + self.c * vector.y,
+            self.b * vector.x + self.d * vector.y,
+        )
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    //Returns None when the transform collapses the plane onto a line or point.
+    pub fn inverse(&self) -> Option<Transform> {
+        let determinant = self.determinant();
+        if approx_zero(determinant) {
+            return None;
+        }
+
+        let a = self.d / determinant;
+        let b = -self.b / determinant;
+        let c = -self.c / determinant;
+        let d = self.a / determinant;
+        Some(Transform::new(
+            a,
+            b,
+            c,
+            d,
+            -(a * self.e + c * self.f),
+            -(b * self.e + d * self.f),
+        ))
+    }
+
+    //True when the transform flips orientation, e.g. a mirror.
+    pub fn is_mirroring(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    //Angle the x axis is turned by.
+    pub fn rotation_angle(&self) -> f64 {
+        self.b.atan2(self.a)
+    }
+
+    //How much lengths grow on average. Exact for uniform scales.
+    pub fn scale_factor(&self) -> f64 {
+        self.determinant().abs().sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_3;
+
+    use super::*;
+
+    fn assert_identity(transform: Transform) {
+        let identity = Transform::identity();
+        for (value, expected) in [
+            (transform.a, identity.a),
+            (transform.b, identity.b),
+            (transform.c, identity.c),
+            (transform.d, identity.d),
+            (transform.e, identity.e),
+            (transform.f, identity.f),
+        ] {
+            assert!((value - expected).abs() < 1e-9, "{transform:?}");
+        }
+    }
+
+    #[test]
+    fn mirror_reflects_across_the_line() {
+        let mirror = Transform::mirror(Point::new(1.0, 1.0), Point::new(3.0, 3.0));
+        assert!(mirror
+            .apply(Point::new(2.0, 0.0))
+            .approx_eq(Point::new(0.0, 2.0)));
+        assert!(mirror
+            .apply(Point::new(5.0, 5.0))
+            .approx_eq(Point::new(5.0, 5.0)));
+        assert!(mirror.is_mirroring());
+        assert_eq!(
+            Transform::mirror(Point::new(1.0, 1.0), Point::new(1.0, 1.0)),
+            Transform::identity()
+        );
+    }
+
+    #[test]
+    fn mirror_twice_is_identity() {
+        let mirror = Transform::mirror(Point::new(-2.0, 1.0), Point::new(3.0, 7.5));
+        assert_identity(mirror.then(&mirror));
+        assert!(!mirror.then(&mirror).is_mirroring());
+    }
+
+    #[test]
+    fn then_applies_in_order() {
+        let translate = Transform::translation(1.0, 0.0);
+        let rotate = Transform::rotation(std::f64::consts::FRAC_PI_2);
+        let point = Point::new(1.0, 0.0);
+        assert!(translate
+            .then(&rotate)
+            .apply(point)
+            .approx_eq(Point::new(0.0, 2.0)));
+        assert!(rotate
+            .then(&translate)
+            .apply(point)
+            .approx_eq(Point::new(1.0, 1.0)));
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        let transform = Transform::rotation_about(Point::new(3.0, -2.0), FRAC_PI_3)
+            .then(&Transform::scale_about(Point::new(1.0, 1.0), 2.0, 0.5))
+            .then(&Transform::mirror(
+                Point::new(0.0, 4.0),
+                Point::new(1.0, 5.0),
+            ))
+            .then(&Transform::translation(-7.0, 3.5));
+        let inverse = transform.inverse().unwrap();
+        assert_identity(transform.then(&inverse));
+        assert_identity(inverse.then(&transform));
+
+        let point = Point::new(12.5, -3.0);
+        assert!(inverse
+            .apply(transform.apply(point))
+            .approx_eq_with(point, 1e-9));
+    }
+
+    #[test]
+    fn collapsing_transform_has_no_inverse() {
+        assert!(Transform::scale(2.0, 0.0).inverse().is_none());
+    }
+}
@@ -1,6 +1,9 @@
+pub mod codec;
 pub mod common;
 pub mod figure;
+pub mod geometry;
 pub mod message;
+pub mod operation;
 
 pub const IP_ADDRESS: &str = "::1";
 pub const PORT: &str = "8080";
@@ -1,28 +1,149 @@
 use serde::{Deserialize, Serialize};
 
-use crate::figure::FigureData;
+use crate::{
+    figure::{block::BlockDefinition, FigureData, FigureId},
+    operation::{LoggedOperation, Operation, RequestId, Sequence},
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+//The protocol this build speaks. Bumped when a change would break older peers.
+pub const PROTOCOL_VERSION: u32 = 2;
+//The oldest protocol a peer may speak.
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+//Optional parts of the protocol, listed in Hello and Welcome. They are names
+//rather than an enum so that a peer can list ones the other side never heard of.
+pub mod feature {
+    //ServerMessage::Ping and ClientMessage::Pong. Clients without it are pinged
+    //with websocket frames instead.
+    pub const HEARTBEAT: &str = "heartbeat";
+    //Everything after the handshake is MessagePack in binary frames, see codec::Codec.
+    pub const BINARY: &str = "binary";
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ServerMessage {
+    //Answer to Hello, with the features both sides have.
+    Welcome {
+        protocol_version: u32,
+        server_name: String,
+        features: Vec<String>,
+    },
     UserJoined(UserId),
-    FigureAdded(FigureData),
+    //Room changes carry their sequence in the room's operation log first,
+    //and figure changes the user who made them after it. Adds also carry the
+    //request id the user sent them with, and so do replaces.
+    FiguresAdded(Sequence, UserId, Option<RequestId>, Vec<FigureData>),
+    FiguresUpdated(Sequence, UserId, Vec<(FigureId, FigureData)>),
+    FiguresDeleted(Sequence, UserId, Vec<FigureId>),
+    FiguresReplaced(
+        Sequence,
+        UserId,
+        Option<RequestId>,
+        Vec<FigureId>,
+        Vec<FigureData>,
+    ),
+    BlockDefined(Sequence, BlockDefinition),
+    //The whole room as of the sequence, sent on join when replaying
+    //the missed changes would take too long.
+    Snapshot(Sequence, Vec<BlockDefinition>, Vec<FigureData>),
     ResponseInfo(ResponseType),
     UserLeft(UserId),
     NotifyUserMousePositionChanged(UserId, f64, f64),
+    //The message named by in_reply_to was rejected. The connection is closed
+    //after a few of these.
+    Error {
+        code: ErrorCode,
+        message: String,
+        in_reply_to: Option<String>,
+    },
+    //Heartbeat, answered with Pong. Carries the milliseconds until the next one,
+    //so the client can tell when one is late.
+    Ping(u64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    //Not a message of this protocol.
+    MalformedMessage,
+    //A valid message at the wrong time, like anything but Join before joining.
+    UnexpectedMessage,
+    //A frame type the server does not take.
+    UnsupportedFrame,
+    RoomUnavailable,
+    //The client speaks a protocol older than the server takes, or skipped Hello.
+    UnsupportedVersion,
+}
+
+impl ServerMessage {
+    pub fn sequence(&self) -> Option<Sequence> {
+        match self {
+            ServerMessage::FiguresAdded(sequence, ..)
+            | ServerMessage::FiguresUpdated(sequence, ..)
+            | ServerMessage::FiguresDeleted(sequence, ..)
+            | ServerMessage::FiguresReplaced(sequence, ..)
+            | ServerMessage::BlockDefined(sequence, ..)
+            | ServerMessage::Snapshot(sequence, ..) => Some(*sequence),
+            _ => None,
+        }
+    }
+}
+
+impl From<LoggedOperation> for ServerMessage {
+    fn from(logged: LoggedOperation) -> Self {
+        let LoggedOperation {
+            sequence,
+            user_id,
+            request_id,
+            operation,
+        } = logged;
+        match operation {
+            Operation::AddFigures(datas) => {
+                ServerMessage::FiguresAdded(sequence, user_id, request_id, datas)
+            }
+            Operation::UpdateFigures(updates) => {
+                ServerMessage::FiguresUpdated(sequence, user_id, updates)
+            }
+            Operation::DeleteFigures(figure_ids) => {
+                ServerMessage::FiguresDeleted(sequence, user_id, figure_ids)
+            }
+            Operation::ReplaceFigures(figure_ids, datas) => {
+                ServerMessage::FiguresReplaced(sequence, user_id, request_id, figure_ids, datas)
+            }
+            Operation::DefineBlock(definition) => ServerMessage::BlockDefined(sequence, definition),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ClientMessage {
+    //Always the first message. Nothing else is taken before the server's Welcome.
+    Hello {
+        protocol_version: u32,
+        client_name: String,
+        capabilities: Vec<String>,
+    },
     Leave,
-    Join(RoomId, UserId),
-    AddFigure(FigureData),
+    //The last sequence this client received, 0 when it has nothing yet.
+    //The server answers with the changes after it.
+    Join(RoomId, UserId, Sequence),
+    AddFigure(RequestId, FigureData),
+    AddFigures(RequestId, Vec<FigureData>),
+    UpdateFigure(FigureId, FigureData),
+    UpdateFigures(Vec<(FigureId, FigureData)>),
+    DeleteFigures(Vec<FigureId>),
+    //Deletes the figures and adds the new ones in one step, like grouping does.
+    ReplaceFigures(RequestId, Vec<FigureId>, Vec<FigureData>),
+    //Adds the block, or replaces the definition with the same name.
+    DefineBlock(BlockDefinition),
     RequestInfo(RequestType),
     NotifyMousePositionChanged(f64, f64),
+    Pong,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum RequestType {
     CurrentFigures,
+    CurrentBlocks,
     CheckRoomExist(RoomId),
     CheckUserExist(RoomId, UserId),
     CurrentSharedUsers,
@@ -31,6 +152,7 @@ pub enum RequestType {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ResponseType {
     CurrentFigures(Vec<FigureData>),
+    CurrentBlocks(Vec<BlockDefinition>),
     CurrentSharedUsers(Vec<String>),
     ResponseRoomExist(bool),
     ResponseUserExist(Option<bool>),
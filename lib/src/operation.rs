@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    figure::{block::BlockDefinition, FigureData, FigureId},
+    message::UserId,
+};
+
+//Numbers the operations of one room in the order they were applied, starting at 1.
+pub type Sequence = u64;
+
+//Chosen by a client for each add or replace it sends and echoed back with the result,
+//so the client can tell which of its requests an echo belongs to.
+pub type RequestId = u64;
+
+//A change to a room as the server applied it, with server assigned ids filled in
+//and requests that changed nothing left out. Replaying them in order rebuilds the room.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Operation {
+    AddFigures(Vec<FigureData>),
+    UpdateFigures(Vec<(FigureId, FigureData)>),
+    DeleteFigures(Vec<FigureId>),
+    //Deletes the figures and adds others in their place, as one change.
+    ReplaceFigures(Vec<FigureId>, Vec<FigureData>),
+    DefineBlock(BlockDefinition),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LoggedOperation {
+    pub sequence: Sequence,
+    pub user_id: UserId,
+    //Only adds and replaces carry one. Missing in operations logged before there were any.
+    #[serde(default)]
+    pub request_id: Option<RequestId>,
+    pub operation: Operation,
+}